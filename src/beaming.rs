@@ -0,0 +1,272 @@
+use crate::{
+    composition::Timeline,
+    note,
+    rhythm::{self, Duration, HasDuration},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Consecutive events of a bar joined by a beam, identified by their offsets within the bar.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BeamGroup {
+    offsets: Vec<Duration>,
+}
+
+impl BeamGroup {
+    /// The offsets of the beamed events in order, including any rests beamed over.
+    #[must_use]
+    pub fn offsets(&self) -> &[Duration] {
+        &self.offsets
+    }
+
+    #[must_use]
+    pub fn start(&self) -> Duration {
+        self.offsets[0]
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Always false; a beam joins at least two events.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+}
+
+/// How the events of a bar are beamed.
+///
+/// Only events shorter than a quarter note are beamed, and a beam never crosses from one beat
+/// group into the next: simple time signatures beam by the beat, compound ones by the dotted
+/// beat and additive ones by their groups.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BeamRules {
+    groups: Vec<Duration>,
+    break_at_rests: bool,
+}
+
+impl BeamRules {
+    /// Beams by the beat groups of `time_signature`, breaking at rests.
+    #[must_use]
+    pub fn new(time_signature: &rhythm::TimeSignature) -> Self {
+        Self {
+            groups: time_signature.beat_durations(),
+            break_at_rests: true,
+        }
+    }
+
+    /// Beams by `groups` instead, such as two half notes to beam four eighths together in 4/4.
+    /// Empty groups are ignored.
+    #[must_use]
+    pub fn with_groups(mut self, groups: Vec<Duration>) -> Self {
+        self.groups = groups
+            .into_iter()
+            .filter(|group| !group.is_zero())
+            .collect();
+        self
+    }
+
+    /// Whether a rest ends a beam; when false, short rests between beamed events are beamed
+    /// over.
+    #[must_use]
+    pub fn with_break_at_rests(mut self, break_at_rests: bool) -> Self {
+        self.break_at_rests = break_at_rests;
+        self
+    }
+
+    #[must_use]
+    pub fn groups(&self) -> &[Duration] {
+        &self.groups
+    }
+
+    #[must_use]
+    pub fn break_at_rests(&self) -> bool {
+        self.break_at_rests
+    }
+
+    /// The end of the beat group that `offset` falls in, repeating the groups after the end of
+    /// the bar.
+    fn group_end(&self, offset: Duration) -> Option<Duration> {
+        let bar_length: Duration = self.groups.iter().sum();
+        if bar_length.is_zero() {
+            return None;
+        }
+        let mut end = Duration::zero();
+        while end <= offset {
+            for group in &self.groups {
+                end += *group;
+                if end > offset {
+                    return Some(end);
+                }
+            }
+        }
+        Some(end)
+    }
+
+    /// Finds the beams of a bar of monophonic events, with offsets relative to the start of the
+    /// bar as given by [`crate::measure::BarMap::bars`]. Events at the same offset share a stem.
+    /// Gaps between events end a beam.
+    #[must_use]
+    pub fn beam(&self, bar: &Timeline<note::Event>) -> Vec<BeamGroup> {
+        let quarter = Duration::new(1, 4).unwrap_or_else(|_| unreachable!("1/4 is not zero"));
+        let mut beams = Vec::new();
+        // offsets of the beam being built, whether each is a rest, and where its group ends
+        let mut current: Vec<(Duration, bool)> = Vec::new();
+        let mut current_group_end = None;
+        let mut expected_offset = Duration::zero();
+
+        let mut finish = |current: &mut Vec<(Duration, bool)>| {
+            while current.last().is_some_and(|(_, is_rest)| *is_rest) {
+                current.pop();
+            }
+            if current.len() >= 2 {
+                beams.push(BeamGroup {
+                    offsets: current.iter().map(|(offset, _)| *offset).collect(),
+                });
+            }
+            current.clear();
+        };
+
+        for offset in bar.offsets() {
+            let events = bar.events_at(offset);
+            let duration = events
+                .iter()
+                .map(HasDuration::duration)
+                .max()
+                .unwrap_or_default();
+            let is_rest = events.iter().all(note::Event::is_rest);
+            let group_end = self.group_end(*offset);
+
+            if *offset != expected_offset || group_end != current_group_end {
+                finish(&mut current);
+            }
+            let beamable = !duration.is_zero() && duration < quarter;
+            if !beamable || (is_rest && (self.break_at_rests || current.is_empty())) {
+                finish(&mut current);
+            } else {
+                current.push((*offset, is_rest));
+                current_group_end = group_end;
+            }
+
+            expected_offset = *offset + duration;
+            if group_end.is_some_and(|end| expected_offset > end) {
+                finish(&mut current);
+            }
+        }
+        finish(&mut current);
+        beams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::note::{Event, Note, Rest};
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::TimeSignature;
+
+    use super::*;
+
+    /// A bar of notes and rests (`false`) played one after another.
+    fn bar(durations: &[(u32, u32, bool)]) -> Timeline<Event> {
+        let mut timeline = Timeline::new();
+        let mut offset = Duration::zero();
+        for (numerator, denominator, is_note) in durations {
+            let length = Duration::new(*numerator, *denominator).unwrap();
+            let event = if *is_note {
+                Note::new(NotePitch::new(NotePitchClass::C, 5), length).into()
+            } else {
+                Rest::new(length).into()
+            };
+            timeline.insert(offset, event);
+            offset += length;
+        }
+        timeline
+    }
+
+    fn beam_lengths(rules: &BeamRules, bar: &Timeline<Event>) -> Vec<(Duration, usize)> {
+        rules
+            .beam(bar)
+            .iter()
+            .map(|beam| (beam.start(), beam.len()))
+            .collect()
+    }
+
+    #[test]
+    fn beams_follow_beat_groups() {
+        let eighths = bar(&[(1, 8, true); 8]);
+        let four_four = BeamRules::new(&TimeSignature::new(4, 4).unwrap());
+        assert_eq!(
+            beam_lengths(&four_four, &eighths),
+            vec![
+                (Duration::zero(), 2),
+                (Duration::new(1, 4).unwrap(), 2),
+                (Duration::new(1, 2).unwrap(), 2),
+                (Duration::new(3, 4).unwrap(), 2)
+            ]
+        );
+        let half_bars = four_four
+            .clone()
+            .with_groups(vec![Duration::new(1, 2).unwrap(); 2]);
+        assert_eq!(
+            beam_lengths(&half_bars, &eighths),
+            vec![(Duration::zero(), 4), (Duration::new(1, 2).unwrap(), 4)]
+        );
+
+        let six_eight = BeamRules::new(&TimeSignature::new(6, 8).unwrap());
+        assert_eq!(
+            beam_lengths(&six_eight, &bar(&[(1, 8, true); 6])),
+            vec![(Duration::zero(), 3), (Duration::new(3, 8).unwrap(), 3)]
+        );
+
+        let additive = BeamRules::new(&TimeSignature::additive(vec![2, 2, 3], 8).unwrap());
+        assert_eq!(
+            beam_lengths(&additive, &bar(&[(1, 8, true); 7])),
+            vec![
+                (Duration::zero(), 2),
+                (Duration::new(1, 4).unwrap(), 2),
+                (Duration::new(1, 2).unwrap(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_notes_rests_and_syncopation_break_beams() {
+        let two_four = BeamRules::new(&TimeSignature::new(2, 4).unwrap());
+        // an eighth tied over the beat by a quarter
+        let syncopated = bar(&[(1, 8, true), (1, 4, true), (1, 8, true)]);
+        assert!(two_four.beam(&syncopated).is_empty());
+
+        let dotted = bar(&[(3, 16, true), (1, 16, true), (1, 4, true)]);
+        assert_eq!(
+            beam_lengths(&two_four, &dotted),
+            vec![(Duration::zero(), 2)]
+        );
+
+        let six_eight = BeamRules::new(&TimeSignature::new(6, 8).unwrap());
+        let with_rest = bar(&[
+            (1, 8, true),
+            (1, 8, false),
+            (1, 8, true),
+            (1, 8, false),
+            (1, 8, true),
+            (1, 8, false),
+        ]);
+        assert!(six_eight.beam(&with_rest).is_empty());
+        let over_rests = six_eight.with_break_at_rests(false);
+        assert_eq!(
+            over_rests.beam(&with_rest),
+            vec![BeamGroup {
+                offsets: vec![
+                    Duration::zero(),
+                    Duration::new(1, 8).unwrap(),
+                    Duration::new(1, 4).unwrap()
+                ]
+            }]
+        );
+    }
+}
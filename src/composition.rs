@@ -1,12 +1,270 @@
-use std::collections::BTreeMap;
+use std::collections::{btree_map, BTreeMap};
+use std::vec;
 
-use crate::rhythm;
+use crate::rhythm::{self, HasDuration};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// Events placed at offsets from the start of a piece.
+///
+/// Any number of events may share an offset, which is how chords and independent voices are
+/// represented. Events at the same offset keep their insertion order.
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Timeline<V> {
-    elements: BTreeMap<rhythm::Duration, V>,
+    elements: BTreeMap<rhythm::Duration, Vec<V>>,
+}
+
+impl<V> Timeline<V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            elements: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, offset: rhythm::Duration, value: V) {
+        self.elements.entry(offset).or_default().push(value);
+    }
+
+    /// Removes and returns every event at `offset`.
+    pub fn remove(&mut self, offset: &rhythm::Duration) -> Vec<V> {
+        self.elements.remove(offset).unwrap_or_default()
+    }
+
+    /// Keeps only the events for which `keep` returns `true`.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&rhythm::Duration, &V) -> bool,
+    {
+        self.elements.retain(|offset, values| {
+            values.retain(|value| keep(offset, value));
+            !values.is_empty()
+        });
+    }
+
+    #[must_use]
+    pub fn events_at(&self, offset: &rhythm::Duration) -> &[V] {
+        self.elements.get(offset).map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterates over the events whose offsets lie in `start..end`, in time order.
+    pub fn events_between(
+        &self,
+        start: rhythm::Duration,
+        end: rhythm::Duration,
+    ) -> impl Iterator<Item = (&rhythm::Duration, &V)> {
+        // BTreeMap::range panics on an inverted range
+        (start < end)
+            .then(|| self.elements.range(start..end))
+            .into_iter()
+            .flatten()
+            .flat_map(|(offset, values)| values.iter().map(move |value| (offset, value)))
+    }
+
+    /// Iterates over every event in time order.
+    pub fn iter(&self) -> impl Iterator<Item = (&rhythm::Duration, &V)> {
+        self.elements
+            .iter()
+            .flat_map(|(offset, values)| values.iter().map(move |value| (offset, value)))
+    }
+
+    /// Iterates over the distinct offsets that hold at least one event.
+    pub fn offsets(&self) -> impl Iterator<Item = &rhythm::Duration> {
+        self.elements.keys()
+    }
+
+    /// The number of events, counting each event at a shared offset separately.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.elements.values().map(Vec::len).sum()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    #[must_use]
+    pub fn first_offset(&self) -> Option<rhythm::Duration> {
+        self.elements.keys().next().copied()
+    }
+
+    #[must_use]
+    pub fn last_offset(&self) -> Option<rhythm::Duration> {
+        self.elements.keys().next_back().copied()
+    }
+
+    /// Moves every event of `other` into this timeline.
+    pub fn merge(&mut self, other: Self) {
+        for (offset, values) in other.elements {
+            self.elements.entry(offset).or_default().extend(values);
+        }
+    }
+
+    /// Returns the timeline with every event moved later by `by`.
+    #[must_use]
+    pub fn shift(self, by: rhythm::Duration) -> Self {
+        Self {
+            elements: self
+                .elements
+                .into_iter()
+                .map(|(offset, values)| (offset + by, values))
+                .collect(),
+        }
+    }
+}
+
+impl<V: Clone> Timeline<V> {
+    /// Copies the events in `start..end` into a new timeline whose offsets are relative to `start`.
+    #[must_use]
+    pub fn slice(&self, start: rhythm::Duration, end: rhythm::Duration) -> Self {
+        let mut sliced = Self::new();
+        for (offset, value) in self.events_between(start, end) {
+            sliced.insert(*offset - start, value.clone());
+        }
+        sliced
+    }
+}
+
+impl<V: HasDuration> Timeline<V> {
+    /// The offset at which the last sounding event finishes, or zero for an empty timeline.
+    #[must_use]
+    pub fn end(&self) -> rhythm::Duration {
+        self.iter()
+            .map(|(offset, value)| *offset + value.duration())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The time from the first event's offset to [`Timeline::end`].
+    #[must_use]
+    pub fn length(&self) -> rhythm::Duration {
+        self.end() - self.first_offset().unwrap_or_default()
+    }
+}
+
+impl<V> Default for Timeline<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(rhythm::Duration, V)> for Timeline<V> {
+    fn from_iter<T: IntoIterator<Item = (rhythm::Duration, V)>>(iter: T) -> Self {
+        let mut timeline = Self::new();
+        timeline.extend(iter);
+        timeline
+    }
+}
+
+impl<V> Extend<(rhythm::Duration, V)> for Timeline<V> {
+    fn extend<T: IntoIterator<Item = (rhythm::Duration, V)>>(&mut self, iter: T) {
+        for (offset, value) in iter {
+            self.insert(offset, value);
+        }
+    }
+}
+
+impl<V> IntoIterator for Timeline<V> {
+    type Item = (rhythm::Duration, V);
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            elements: self.elements.into_iter(),
+            current: None,
+        }
+    }
+}
+
+/// An owning iterator over the events of a [`Timeline`] in time order.
+pub struct IntoIter<V> {
+    elements: btree_map::IntoIter<rhythm::Duration, Vec<V>>,
+    current: Option<(rhythm::Duration, vec::IntoIter<V>)>,
+}
+
+impl<V> Iterator for IntoIter<V> {
+    type Item = (rhythm::Duration, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((offset, values)) = &mut self.current {
+                if let Some(value) = values.next() {
+                    return Some((*offset, value));
+                }
+            }
+            let (offset, values) = self.elements.next()?;
+            self.current = Some((offset, values.into_iter()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::note::Note;
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::Duration;
+
+    use super::*;
+
+    fn quarter_note(class: NotePitchClass) -> Note {
+        Note::new(NotePitch::new(class, 4), Duration::new(1, 4).unwrap())
+    }
+
+    #[test]
+    fn events_at_same_offset_are_kept() {
+        let mut timeline = Timeline::new();
+        timeline.insert(Duration::zero(), quarter_note(NotePitchClass::C));
+        timeline.insert(Duration::zero(), quarter_note(NotePitchClass::E));
+        timeline.insert(
+            Duration::new(1, 4).unwrap(),
+            quarter_note(NotePitchClass::G),
+        );
+
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline.events_at(&Duration::zero()).len(), 2);
+        assert_eq!(timeline.end(), Duration::new(1, 2).unwrap());
+
+        let removed = timeline.remove(&Duration::zero());
+        assert_eq!(removed.len(), 2);
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline.first_offset(), Some(Duration::new(1, 4).unwrap()));
+    }
+
+    #[test]
+    fn events_between_is_half_open_and_ordered() {
+        let timeline: Timeline<u32> = (0..8)
+            .map(|i| (Duration::new(1, 8).unwrap() * i, i))
+            .collect();
+
+        let found: Vec<u32> = timeline
+            .events_between(Duration::new(1, 4).unwrap(), Duration::new(1, 2).unwrap())
+            .map(|(_, value)| *value)
+            .collect();
+        assert_eq!(found, vec![2, 3]);
+
+        let everything: Vec<u32> = timeline.into_iter().map(|(_, value)| value).collect();
+        assert_eq!(everything, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn merge_shift_and_slice() {
+        let quarter = Duration::new(1, 4).unwrap();
+        let mut melody = Timeline::new();
+        melody.insert(Duration::zero(), quarter_note(NotePitchClass::C));
+        melody.insert(quarter, quarter_note(NotePitchClass::D));
+
+        let mut bass = Timeline::new();
+        bass.insert(Duration::zero(), quarter_note(NotePitchClass::A));
+
+        melody.merge(bass.shift(quarter));
+        assert_eq!(melody.events_at(&quarter).len(), 2);
+
+        let sliced = melody.slice(quarter, quarter * 2);
+        assert_eq!(sliced.len(), 2);
+        assert_eq!(sliced.first_offset(), Some(Duration::zero()));
+        assert_eq!(sliced.length(), quarter);
+    }
 }
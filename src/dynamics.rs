@@ -0,0 +1,420 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    composition::Timeline,
+    note::{Articulation, Note},
+    rhythm::Duration,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How loudly to play, from a marking or as a MIDI velocity.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Dynamic {
+    Ppp,
+    Pp,
+    P,
+    Mp,
+    Mf,
+    F,
+    Ff,
+    Fff,
+    /// A sudden accent on one note, after which the previous dynamic continues.
+    Sfz,
+    /// A MIDI velocity for one note, in `1..128`.
+    Velocity(u8),
+}
+
+impl Dynamic {
+    /// The dynamic markings from softest to loudest.
+    pub const LEVELS: [Self; 8] = [
+        Self::Ppp,
+        Self::Pp,
+        Self::P,
+        Self::Mp,
+        Self::Mf,
+        Self::F,
+        Self::Ff,
+        Self::Fff,
+    ];
+
+    /// Whether the dynamic lasts until the next one, rather than applying to one note.
+    #[must_use]
+    pub fn is_level(self) -> bool {
+        !matches!(self, Self::Sfz | Self::Velocity(_))
+    }
+}
+
+impl Display for Dynamic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ppp => f.write_str("ppp"),
+            Self::Pp => f.write_str("pp"),
+            Self::P => f.write_str("p"),
+            Self::Mp => f.write_str("mp"),
+            Self::Mf => f.write_str("mf"),
+            Self::F => f.write_str("f"),
+            Self::Ff => f.write_str("ff"),
+            Self::Fff => f.write_str("fff"),
+            Self::Sfz => f.write_str("sfz"),
+            Self::Velocity(velocity) => write!(f, "{velocity}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HairpinKind {
+    Crescendo,
+    Diminuendo,
+}
+
+/// A gradual change of dynamic lasting `length` from its offset in a timeline.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hairpin {
+    kind: HairpinKind,
+    length: Duration,
+    target: Option<Dynamic>,
+}
+
+impl Hairpin {
+    #[must_use]
+    pub fn new(kind: HairpinKind, length: Duration) -> Self {
+        Self {
+            kind,
+            length,
+            target: None,
+        }
+    }
+
+    /// The dynamic reached at the end of the hairpin. Without one the hairpin moves to the next
+    /// dynamic level.
+    #[must_use]
+    pub fn with_target(self, target: Dynamic) -> Self {
+        Self {
+            target: Some(target),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> HairpinKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn length(&self) -> Duration {
+        self.length
+    }
+
+    #[must_use]
+    pub fn target(&self) -> Option<Dynamic> {
+        self.target
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewVelocityMapError {
+    /// A velocity outside `1..128`.
+    InvalidVelocity(u8),
+    /// The levels get softer from one dynamic to the next.
+    Decreasing,
+}
+
+impl Display for NewVelocityMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidVelocity(velocity) => write!(f, "invalid velocity {velocity}"),
+            Self::Decreasing => f.write_str("dynamic levels must not get softer from ppp to fff"),
+        }
+    }
+}
+
+impl Error for NewVelocityMapError {}
+
+/// MIDI velocities for the dynamics and accents of one instrument.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VelocityMap {
+    levels: [u8; 8],
+    sforzando: u8,
+    accent: u8,
+    marcato: u8,
+}
+
+impl VelocityMap {
+    /// `levels` are the velocities of [`Dynamic::LEVELS`], from ppp to fff.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a velocity is outside `1..128` or the levels get softer.
+    pub fn new(levels: [u8; 8], sforzando: u8) -> Result<Self, NewVelocityMapError> {
+        if let Some(velocity) = levels
+            .iter()
+            .chain([&sforzando])
+            .find(|velocity| !(1..=127).contains(*velocity))
+        {
+            return Err(NewVelocityMapError::InvalidVelocity(*velocity));
+        }
+        if levels.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err(NewVelocityMapError::Decreasing);
+        }
+        Ok(Self {
+            levels,
+            sforzando,
+            ..Self::default()
+        })
+    }
+
+    /// The velocity added to accented notes.
+    #[must_use]
+    pub fn with_accent(self, accent: u8) -> Self {
+        Self { accent, ..self }
+    }
+
+    /// The velocity added to notes marked marcato.
+    #[must_use]
+    pub fn with_marcato(self, marcato: u8) -> Self {
+        Self { marcato, ..self }
+    }
+
+    #[must_use]
+    pub fn levels(&self) -> &[u8; 8] {
+        &self.levels
+    }
+
+    #[must_use]
+    pub fn sforzando(&self) -> u8 {
+        self.sforzando
+    }
+
+    #[must_use]
+    pub fn accent(&self) -> u8 {
+        self.accent
+    }
+
+    #[must_use]
+    pub fn marcato(&self) -> u8 {
+        self.marcato
+    }
+
+    #[must_use]
+    pub fn velocity(&self, dynamic: Dynamic) -> u8 {
+        match dynamic {
+            Dynamic::Sfz => self.sforzando,
+            Dynamic::Velocity(velocity) => velocity.clamp(1, 127),
+            level => {
+                let index = Dynamic::LEVELS
+                    .iter()
+                    .position(|candidate| *candidate == level)
+                    .unwrap_or_else(|| unreachable!("every other dynamic is a level"));
+                self.levels[index]
+            }
+        }
+    }
+
+    /// The first level louder or softer than `velocity`, or the loudest or softest velocity
+    /// there is.
+    fn next_level(&self, velocity: u8, kind: HairpinKind) -> u8 {
+        match kind {
+            HairpinKind::Crescendo => self
+                .levels
+                .iter()
+                .copied()
+                .find(|level| *level > velocity)
+                .unwrap_or(127),
+            HairpinKind::Diminuendo => self
+                .levels
+                .iter()
+                .rev()
+                .copied()
+                .find(|level| *level < velocity)
+                .unwrap_or(1),
+        }
+    }
+
+    /// The velocity of every note of `track`, in timeline order.
+    ///
+    /// Notes are played at `initial` until the first dynamic level. Hairpins move smoothly from
+    /// the level in effect where they start, and a new level cancels them. Accents and marcatos
+    /// are added on top.
+    #[must_use]
+    pub fn velocities(
+        &self,
+        track: &Timeline<Note>,
+        hairpins: &Timeline<Hairpin>,
+        initial: u8,
+    ) -> Vec<u8> {
+        struct Active {
+            start: Duration,
+            end: Duration,
+            from: u8,
+            to: u8,
+        }
+
+        let mut running = initial.clamp(1, 127);
+        let mut active: Option<Active> = None;
+        let mut pending = hairpins.iter().peekable();
+        let mut velocities = Vec::with_capacity(track.len());
+
+        for (offset, note) in track.iter() {
+            let mut activate = |running: &mut u8, active: &mut Option<Active>, at_offset: bool| {
+                while let Some((start, hairpin)) =
+                    pending.next_if(|(start, _)| *start < offset || (at_offset && *start == offset))
+                {
+                    let to = hairpin.target.map_or_else(
+                        || self.next_level(*running, hairpin.kind),
+                        |target| self.velocity(target),
+                    );
+                    *active = Some(Active {
+                        start: *start,
+                        end: *start + hairpin.length,
+                        from: *running,
+                        to,
+                    });
+                    if active.as_ref().is_some_and(|active| active.end <= *offset) {
+                        *running = to;
+                        *active = None;
+                    }
+                }
+            };
+
+            if active.as_ref().is_some_and(|active| active.end <= *offset) {
+                running = active.take().map_or(running, |active| active.to);
+            }
+            activate(&mut running, &mut active, false);
+            if let Some(dynamic) = note.dynamic().filter(|dynamic| dynamic.is_level()) {
+                running = self.velocity(dynamic);
+                active = None;
+            }
+            activate(&mut running, &mut active, true);
+
+            let mut velocity = match &active {
+                Some(active) => {
+                    let progress = (*offset - active.start).ratio().to_f64()
+                        / (active.end - active.start).ratio().to_f64();
+                    let velocity = f64::from(active.from)
+                        + (f64::from(active.to) - f64::from(active.from)) * progress;
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let velocity = velocity.round().clamp(1.0, 127.0) as u8;
+                    velocity
+                }
+                None => running,
+            };
+            if let Some(dynamic) = note.dynamic().filter(|dynamic| !dynamic.is_level()) {
+                velocity = self.velocity(dynamic);
+            }
+            if note.has_articulation(Articulation::Accent) {
+                velocity = velocity.saturating_add(self.accent);
+            }
+            if note.has_articulation(Articulation::Marcato) {
+                velocity = velocity.saturating_add(self.marcato);
+            }
+            velocities.push(velocity.min(127));
+        }
+        velocities
+    }
+}
+
+impl Default for VelocityMap {
+    fn default() -> Self {
+        Self {
+            levels: [16, 32, 48, 64, 80, 96, 112, 127],
+            sforzando: 120,
+            accent: 16,
+            marcato: 24,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::{NotePitch, NotePitchClass};
+
+    use super::*;
+
+    fn quarters(count: u32) -> Vec<(Duration, Note)> {
+        (0..count)
+            .map(|index| {
+                (
+                    Duration::new(1, 4).unwrap() * index,
+                    Note::new(
+                        NotePitch::new(NotePitchClass::C, 4),
+                        Duration::new(1, 4).unwrap(),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    fn timeline(notes: Vec<(Duration, Note)>) -> Timeline<Note> {
+        let mut timeline = Timeline::new();
+        for (offset, note) in notes {
+            timeline.insert(offset, note);
+        }
+        timeline
+    }
+
+    #[test]
+    fn dynamics_and_accents() {
+        let mut notes = quarters(5);
+        notes[1].1 = notes[1].1.clone().with_dynamic(Dynamic::P);
+        notes[2].1 = notes[2].1.clone().with_dynamic(Dynamic::Sfz);
+        notes[3].1 = notes[3]
+            .1
+            .clone()
+            .with_articulation(Articulation::Accent)
+            .with_articulation(Articulation::Accent);
+        notes[4].1 = notes[4].1.clone().with_dynamic(Dynamic::Velocity(100));
+        let map = VelocityMap::default();
+        assert_eq!(
+            map.velocities(&timeline(notes.clone()), &Timeline::new(), 64),
+            vec![64, 48, 120, 64, 100]
+        );
+        assert_eq!(notes[3].1.articulations(), &[Articulation::Accent]);
+        assert_eq!(Dynamic::Fff.to_string(), "fff");
+
+        let quiet = VelocityMap::new([10, 20, 30, 40, 50, 60, 70, 80], 90)
+            .unwrap()
+            .with_accent(5);
+        assert_eq!(quiet.velocity(Dynamic::Mf), 50);
+        assert_eq!(
+            quiet.velocities(&timeline(notes), &Timeline::new(), 64),
+            vec![64, 30, 90, 35, 100]
+        );
+        assert_eq!(
+            VelocityMap::new([10, 20, 30, 40, 50, 60, 70, 0], 90),
+            Err(NewVelocityMapError::InvalidVelocity(0))
+        );
+        assert_eq!(
+            VelocityMap::new([10, 20, 30, 40, 50, 60, 70, 65], 90),
+            Err(NewVelocityMapError::Decreasing)
+        );
+    }
+
+    #[test]
+    fn hairpins() {
+        let mut notes = quarters(6);
+        notes[0].1 = notes[0].1.clone().with_dynamic(Dynamic::P);
+        let mut hairpins = Timeline::new();
+        hairpins.insert(
+            Duration::zero(),
+            Hairpin::new(HairpinKind::Crescendo, Duration::new(1, 1).unwrap())
+                .with_target(Dynamic::F),
+        );
+        hairpins.insert(
+            Duration::new(5, 4).unwrap(),
+            Hairpin::new(HairpinKind::Diminuendo, Duration::new(1, 4).unwrap()),
+        );
+        let map = VelocityMap::default();
+        // p to f over four beats, then f, then the start of a diminuendo to mf
+        assert_eq!(
+            map.velocities(&timeline(notes), &hairpins, 64),
+            vec![48, 60, 72, 84, 96, 96]
+        );
+    }
+}
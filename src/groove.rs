@@ -0,0 +1,357 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    composition::Timeline,
+    quantize::PerformedNote,
+    rhythm::{self, Duration},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How one subdivision of a groove is played.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GrooveStep {
+    timing: f64,
+    velocity: i8,
+}
+
+impl GrooveStep {
+    /// `timing` is how late the subdivision is played, as a fraction of a subdivision; negative
+    /// values play early. `velocity` is added to the velocity of its notes.
+    #[must_use]
+    pub fn new(timing: f64, velocity: i8) -> Self {
+        Self { timing, velocity }
+    }
+
+    #[must_use]
+    pub fn timing(&self) -> f64 {
+        self.timing
+    }
+
+    #[must_use]
+    pub fn velocity(&self) -> i8 {
+        self.velocity
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewGrooveTemplateError {
+    ZeroSubdivision,
+    NoSteps,
+    InvalidSwing,
+}
+
+impl Display for NewGrooveTemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZeroSubdivision => f.write_str("a groove's subdivision cannot be empty"),
+            Self::NoSteps => f.write_str("a groove needs at least one step"),
+            Self::InvalidSwing => f.write_str("a swing's share must be between 0 and 1"),
+        }
+    }
+}
+
+impl Error for NewGrooveTemplateError {}
+
+/// Timing and velocity offsets for each subdivision of a repeating span, applied when rendering
+/// a timeline while its exact offsets stay untouched.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GrooveTemplate {
+    subdivision: Duration,
+    steps: Vec<GrooveStep>,
+}
+
+impl GrooveTemplate {
+    /// A groove of one step per `subdivision`, repeating after the last step.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `subdivision` is zero or there are no steps.
+    pub fn new(
+        subdivision: Duration,
+        steps: Vec<GrooveStep>,
+    ) -> Result<Self, NewGrooveTemplateError> {
+        if subdivision.is_zero() {
+            return Err(NewGrooveTemplateError::ZeroSubdivision);
+        }
+        if steps.is_empty() {
+            return Err(NewGrooveTemplateError::NoSteps);
+        }
+        Ok(Self { subdivision, steps })
+    }
+
+    /// Swings pairs of `subdivision`s so that the first takes `share` of the pair: 0.5 is
+    /// straight, 0.6 is a 60% swing and 2/3 a 2:1 swing. Shares below 0.5 play the second
+    /// subdivision early.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `subdivision` is zero or `share` is not strictly between 0 and 1.
+    pub fn swing(subdivision: Duration, share: f64) -> Result<Self, NewGrooveTemplateError> {
+        if !(share > 0.0 && share < 1.0) {
+            return Err(NewGrooveTemplateError::InvalidSwing);
+        }
+        Self::new(
+            subdivision,
+            vec![GrooveStep::default(), GrooveStep::new(2.0 * share - 1.0, 0)],
+        )
+    }
+
+    /// Learns a groove from `notes` played in `rhythm`: each step's timing is the average
+    /// deviation of the notes nearest it from the straight grid, and its velocity how much
+    /// louder they were than the average note. Steps without notes are left straight.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `subdivision` is zero or `steps` is zero.
+    pub fn extract(
+        notes: &[PerformedNote],
+        rhythm: &rhythm::Rhythm,
+        subdivision: Duration,
+        steps: usize,
+    ) -> Result<Self, NewGrooveTemplateError> {
+        let mut template = Self::new(subdivision, vec![GrooveStep::default(); steps])?;
+        if notes.is_empty() {
+            return Ok(template);
+        }
+        let beat = rhythm.beat_assignment().beat_duration().ratio().to_f64();
+        let step = subdivision.ratio().to_f64();
+        let mut sums = vec![(0.0, 0.0, 0u32); steps];
+        let mut total_velocity = 0.0;
+        for note in notes {
+            let position = note.onset() / rhythm.tempo().one_beat_seconds() * beat;
+            let nearest = (position / step).round();
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let index = (nearest as usize) % steps;
+            let sum = &mut sums[index];
+            sum.0 += position / step - nearest;
+            sum.1 += f64::from(note.velocity());
+            sum.2 += 1;
+            total_velocity += f64::from(note.velocity());
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let average_velocity = total_velocity / notes.len() as f64;
+        for (groove_step, (timing, velocity, count)) in template.steps.iter_mut().zip(sums) {
+            if count > 0 {
+                let count = f64::from(count);
+                #[allow(clippy::cast_possible_truncation)]
+                let velocity = (velocity / count - average_velocity)
+                    .round()
+                    .clamp(f64::from(i8::MIN), f64::from(i8::MAX))
+                    as i8;
+                *groove_step = GrooveStep::new(timing / count, velocity);
+            }
+        }
+        Ok(template)
+    }
+
+    #[must_use]
+    pub fn subdivision(&self) -> Duration {
+        self.subdivision
+    }
+
+    #[must_use]
+    pub fn steps(&self) -> &[GrooveStep] {
+        &self.steps
+    }
+
+    /// The span after which the groove repeats.
+    #[must_use]
+    pub fn length(&self) -> Duration {
+        self.subdivision * u32::try_from(self.steps.len()).unwrap_or(u32::MAX)
+    }
+
+    fn step(&self, index: u64) -> GrooveStep {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (index % self.steps.len() as u64) as usize;
+        self.steps[index]
+    }
+
+    /// How far the groove moves `offset`, in whole notes. Offsets between subdivisions move in
+    /// proportion to the subdivisions either side.
+    #[must_use]
+    pub fn displacement(&self, offset: Duration) -> f64 {
+        let steps = *offset.ratio() / *self.subdivision.ratio();
+        let index = u64::from(steps.floor());
+        let before = self.step(index).timing;
+        let timing = if steps.denominator() == 1 {
+            before
+        } else {
+            let fraction = steps.to_f64() - f64::from(steps.floor());
+            before + (self.step(index + 1).timing - before) * fraction
+        };
+        timing * self.subdivision.ratio().to_f64()
+    }
+
+    /// The velocity offset of `offset`: its step's if it falls on a subdivision, otherwise none.
+    #[must_use]
+    pub fn velocity_offset(&self, offset: Duration) -> i8 {
+        let steps = *offset.ratio() / *self.subdivision.ratio();
+        if steps.denominator() == 1 {
+            self.step(u64::from(steps.numerator())).velocity
+        } else {
+            0
+        }
+    }
+
+    /// Renders every event of `timeline` with the groove, in timeline order.
+    #[must_use]
+    pub fn apply<V: Clone>(&self, timeline: &Timeline<V>) -> Vec<GroovedEvent<V>> {
+        timeline
+            .iter()
+            .map(|(offset, value)| GroovedEvent {
+                offset: *offset,
+                position: offset.ratio().to_f64() + self.displacement(*offset),
+                velocity_offset: self.velocity_offset(*offset),
+                value: value.clone(),
+            })
+            .collect()
+    }
+}
+
+/// An event of a timeline as rendered with a groove.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GroovedEvent<V> {
+    offset: Duration,
+    position: f64,
+    velocity_offset: i8,
+    value: V,
+}
+
+impl<V> GroovedEvent<V> {
+    /// The exact offset of the event in its timeline.
+    #[must_use]
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Where the event is played, in whole notes.
+    #[must_use]
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    #[must_use]
+    pub fn velocity_offset(&self) -> i8 {
+        self.velocity_offset
+    }
+
+    #[must_use]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// When the event is played in `rhythm`, in seconds.
+    #[must_use]
+    pub fn seconds(&self, rhythm: &rhythm::Rhythm) -> f64 {
+        self.position / rhythm.beat_assignment().beat_duration().ratio().to_f64()
+            * rhythm.tempo().one_beat_seconds()
+    }
+
+    /// `velocity` with the groove's offset added, kept within the MIDI range of `1..128`.
+    #[must_use]
+    pub fn velocity(&self, velocity: u8) -> u8 {
+        let velocity = i16::from(velocity) + i16::from(self.velocity_offset);
+        u8::try_from(velocity.clamp(1, 127)).unwrap_or_else(|_| unreachable!("clamped to 1..128"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::note::Note;
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::{BeatAssignment, Rhythm, Tempo};
+
+    use super::*;
+
+    fn eighths(count: u32) -> Timeline<Note> {
+        let mut timeline = Timeline::new();
+        for index in 0..count {
+            timeline.insert(
+                Duration::new(1, 8).unwrap() * index,
+                Note::new(
+                    NotePitch::new(NotePitchClass::C, 4),
+                    Duration::new(1, 8).unwrap(),
+                ),
+            );
+        }
+        timeline
+    }
+
+    #[test]
+    fn swing_moves_off_beats_only() {
+        let swing = GrooveTemplate::swing(Duration::new(1, 8).unwrap(), 2.0 / 3.0).unwrap();
+        let rendered = swing.apply(&eighths(4));
+        let positions: Vec<f64> = rendered.iter().map(GroovedEvent::position).collect();
+        for (actual, expected) in positions.iter().zip([0.0, 1.0 / 6.0, 0.25, 5.0 / 12.0]) {
+            assert!((*actual - expected).abs() < 1e-9);
+        }
+        assert_eq!(rendered[1].offset(), Duration::new(1, 8).unwrap());
+
+        let quarter_beats = Rhythm::new(
+            Tempo::new(60.0).unwrap(),
+            BeatAssignment::new(Duration::new(1, 4).unwrap()),
+        );
+        assert!((rendered[1].seconds(&quarter_beats) - 2.0 / 3.0).abs() < 1e-9);
+
+        // a sixteenth between the swung eighths moves halfway as far as the off-beat
+        assert!((swing.displacement(Duration::new(1, 16).unwrap()) - 1.0 / 48.0).abs() < 1e-9);
+
+        let sixty_percent = GrooveTemplate::swing(Duration::new(1, 8).unwrap(), 0.6).unwrap();
+        assert!((sixty_percent.displacement(Duration::new(3, 8).unwrap()) - 0.025).abs() < 1e-9);
+        for share in [f64::NAN, 0.0, 1.0, -0.5] {
+            assert_eq!(
+                GrooveTemplate::swing(Duration::new(1, 8).unwrap(), share),
+                Err(NewGrooveTemplateError::InvalidSwing)
+            );
+        }
+    }
+
+    #[test]
+    fn extracting_and_applying_a_groove() {
+        let rhythm = Rhythm::new(
+            Tempo::new(60.0).unwrap(),
+            BeatAssignment::new(Duration::new(1, 4).unwrap()),
+        );
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        // sixteenths, the second of each pair late and soft
+        let notes: Vec<PerformedNote> = (0..8)
+            .map(|index| {
+                let onset = f64::from(index) * 0.25 + if index % 2 == 1 { 0.05 } else { 0.0 };
+                let velocity = if index % 2 == 1 { 80 } else { 100 };
+                PerformedNote::new(c4, onset, 0.2)
+                    .unwrap()
+                    .with_velocity(velocity)
+            })
+            .collect();
+        let groove =
+            GrooveTemplate::extract(&notes, &rhythm, Duration::new(1, 16).unwrap(), 2).unwrap();
+        assert!(groove.steps()[0].timing().abs() < 1e-9);
+        assert!((groove.steps()[1].timing() - 0.2).abs() < 1e-9);
+        assert_eq!(groove.steps()[0].velocity(), 10);
+        assert_eq!(groove.steps()[1].velocity(), -10);
+        assert_eq!(groove.length(), Duration::new(1, 8).unwrap());
+
+        let rendered = groove.apply(&eighths(2));
+        assert_eq!(rendered[0].velocity(64), 74);
+        let mut sixteenths = Timeline::new();
+        sixteenths.insert(Duration::new(1, 16).unwrap(), c4);
+        let rendered = groove.apply(&sixteenths);
+        assert!((rendered[0].position() - (1.0 / 16.0 + 0.2 / 16.0)).abs() < 1e-9);
+        assert_eq!(rendered[0].velocity(1), 1);
+
+        assert_eq!(
+            GrooveTemplate::new(Duration::zero(), vec![GrooveStep::default()]),
+            Err(NewGrooveTemplateError::ZeroSubdivision)
+        );
+        assert_eq!(
+            GrooveTemplate::extract(&notes, &rhythm, Duration::new(1, 16).unwrap(), 0),
+            Err(NewGrooveTemplateError::NoSteps)
+        );
+    }
+}
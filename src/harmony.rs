@@ -1,6 +1,14 @@
-use std::collections::{BTreeSet, HashSet};
+use std::{
+    collections::{BTreeSet, HashSet},
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+};
 
-use crate::{interval::SemitoneInterval, pitch};
+use crate::{
+    interval::{SemitoneInterval, Semitones},
+    key, pitch,
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -37,7 +45,9 @@ pub struct RootedChordClass {
 }
 
 impl RootedChordClass {
-    #[must_use]
+    /// # Errors
+    ///
+    /// Returns [`NewChordError::RootNotInChord`] if `root` is not one of the chord's pitch classes.
     pub fn new(
         chord_class: ChordClass,
         root: pitch::NotePitchClass,
@@ -85,7 +95,9 @@ pub struct RootedChord {
 }
 
 impl RootedChord {
-    #[must_use]
+    /// # Errors
+    ///
+    /// Returns [`NewChordError::RootNotInChord`] if `root` is not one of the chord's pitches.
     pub fn new(chord: Chord, root: pitch::NotePitch) -> Result<Self, NewChordError> {
         if !chord.note_pitches().contains(&root) {
             return Err(NewChordError::RootNotInChord);
@@ -121,3 +133,961 @@ impl ChordPattern {
         &self.intervals
     }
 }
+
+/// A chord type that can be recognized from its pitch classes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Suspended2,
+    Suspended4,
+    Major6,
+    Minor6,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    Dominant7Suspended4,
+    Dominant9,
+    Major9,
+    Minor9,
+    Dominant11,
+    Dominant13,
+    Add9,
+    MinorAdd9,
+    /// A dominant seventh whose fifth and ninth are altered: any of b9, #9, #11 (b5) and b13 (#5)
+    /// with no natural fifth, ninth or thirteenth.
+    Altered,
+}
+
+impl ChordQuality {
+    pub const ALL: [ChordQuality; 23] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Suspended2,
+        ChordQuality::Suspended4,
+        ChordQuality::Major6,
+        ChordQuality::Minor6,
+        ChordQuality::Dominant7,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::MinorMajor7,
+        ChordQuality::HalfDiminished7,
+        ChordQuality::Diminished7,
+        ChordQuality::Dominant7Suspended4,
+        ChordQuality::Dominant9,
+        ChordQuality::Major9,
+        ChordQuality::Minor9,
+        ChordQuality::Dominant11,
+        ChordQuality::Dominant13,
+        ChordQuality::Add9,
+        ChordQuality::MinorAdd9,
+        ChordQuality::Altered,
+    ];
+
+    /// Semitones above the root that must be present, in stacked-thirds order.
+    #[must_use]
+    pub fn required_semitones(self) -> &'static [Semitones] {
+        match self {
+            Self::Major => &[0, 4, 7],
+            Self::Minor => &[0, 3, 7],
+            Self::Diminished => &[0, 3, 6],
+            Self::Augmented => &[0, 4, 8],
+            Self::Suspended2 => &[0, 2, 7],
+            Self::Suspended4 => &[0, 5, 7],
+            Self::Major6 => &[0, 4, 9],
+            Self::Minor6 => &[0, 3, 9],
+            Self::Dominant7 | Self::Altered => &[0, 4, 10],
+            Self::Major7 => &[0, 4, 11],
+            Self::Minor7 => &[0, 3, 10],
+            Self::MinorMajor7 => &[0, 3, 11],
+            Self::HalfDiminished7 => &[0, 3, 6, 10],
+            Self::Diminished7 => &[0, 3, 6, 9],
+            Self::Dominant7Suspended4 => &[0, 5, 10],
+            Self::Dominant9 => &[0, 4, 10, 2],
+            Self::Major9 => &[0, 4, 11, 2],
+            Self::Minor9 => &[0, 3, 10, 2],
+            Self::Dominant11 => &[0, 10, 2, 5],
+            Self::Dominant13 => &[0, 4, 10, 9],
+            Self::Add9 => &[0, 4, 7, 2],
+            Self::MinorAdd9 => &[0, 3, 7, 2],
+        }
+    }
+
+    /// Semitones above the root that are part of the chord but are commonly left out.
+    #[must_use]
+    pub fn optional_semitones(self) -> &'static [Semitones] {
+        match self {
+            Self::Major6
+            | Self::Minor6
+            | Self::Dominant7
+            | Self::Major7
+            | Self::Minor7
+            | Self::MinorMajor7
+            | Self::Dominant7Suspended4
+            | Self::Dominant9
+            | Self::Major9
+            | Self::Minor9 => &[7],
+            Self::Dominant11 => &[4, 7],
+            Self::Dominant13 => &[7, 2],
+            Self::Altered => &[1, 3, 6, 8],
+            _ => &[],
+        }
+    }
+
+    /// The suffix written after the root in a chord symbol, e.g. `maj7` or `m7b5`.
+    #[must_use]
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Major => "",
+            Self::Minor => "m",
+            Self::Diminished => "dim",
+            Self::Augmented => "aug",
+            Self::Suspended2 => "sus2",
+            Self::Suspended4 => "sus4",
+            Self::Major6 => "6",
+            Self::Minor6 => "m6",
+            Self::Dominant7 => "7",
+            Self::Major7 => "maj7",
+            Self::Minor7 => "m7",
+            Self::MinorMajor7 => "m(maj7)",
+            Self::HalfDiminished7 => "m7b5",
+            Self::Diminished7 => "dim7",
+            Self::Dominant7Suspended4 => "7sus4",
+            Self::Dominant9 => "9",
+            Self::Major9 => "maj9",
+            Self::Minor9 => "m9",
+            Self::Dominant11 => "11",
+            Self::Dominant13 => "13",
+            Self::Add9 => "add9",
+            Self::MinorAdd9 => "m(add9)",
+            Self::Altered => "7alt",
+        }
+    }
+
+    /// How often the quality is used, so that ambiguous pitch class sets prefer everyday readings.
+    fn commonness(self) -> u8 {
+        match self {
+            Self::Major | Self::Minor => 10,
+            Self::Dominant7 | Self::Major7 | Self::Minor7 => 9,
+            Self::Diminished | Self::HalfDiminished7 | Self::Diminished7 => 8,
+            Self::Augmented | Self::Suspended4 | Self::Major6 => 7,
+            Self::Suspended2
+            | Self::Minor6
+            | Self::Dominant7Suspended4
+            | Self::Dominant9
+            | Self::Add9 => 6,
+            Self::MinorMajor7
+            | Self::Major9
+            | Self::Minor9
+            | Self::Dominant13
+            | Self::MinorAdd9 => 5,
+            Self::Dominant11 => 4,
+            Self::Altered => 3,
+        }
+    }
+
+    /// How many optional tones are missing if `semitones` (a bit set of semitones above the root)
+    /// is a voicing of this quality, or `None` if it is not.
+    fn omissions(self, semitones: u16) -> Option<usize> {
+        let mask = |tones: &[Semitones]| tones.iter().fold(0u16, |mask, tone| mask | 1 << tone);
+        let required = mask(self.required_semitones());
+        let optional = mask(self.optional_semitones());
+        if semitones & required != required || semitones & !(required | optional) != 0 {
+            return None;
+        }
+        if self == Self::Altered {
+            // at least one alteration is what sets the chord apart from a plain dominant seventh
+            return (semitones & optional != 0).then_some(0);
+        }
+        Some((optional & !semitones).count_ones() as usize)
+    }
+
+    /// The inversion a chord of this quality is in when the bass is `semitones` above the root,
+    /// or `None` if the bass is an added tone or extension.
+    #[must_use]
+    pub fn inversion_for_bass(self, semitones: Semitones) -> Option<Inversion> {
+        match semitones.rem_euclid(12) {
+            0 => Some(Inversion::Root),
+            3 | 4 => Some(Inversion::First),
+            2 if self == Self::Suspended2 => Some(Inversion::First),
+            5 if matches!(self, Self::Suspended4 | Self::Dominant7Suspended4) => {
+                Some(Inversion::First)
+            }
+            6..=8 => Some(Inversion::Second),
+            9 if matches!(self, Self::Major6 | Self::Minor6 | Self::Diminished7) => {
+                Some(Inversion::Third)
+            }
+            10 | 11 => Some(Inversion::Third),
+            _ => None,
+        }
+    }
+}
+
+/// Which chord tone is in the bass.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Inversion {
+    Root,
+    First,
+    Second,
+    Third,
+}
+
+/// One possible reading of a set of pitch classes as a chord.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordCandidate {
+    root: pitch::NotePitchClass,
+    quality: ChordQuality,
+    omissions: usize,
+}
+
+impl ChordCandidate {
+    #[must_use]
+    pub fn root(&self) -> pitch::NotePitchClass {
+        self.root
+    }
+
+    #[must_use]
+    pub fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    /// How many of the quality's optional tones, such as the fifth of a seventh chord, are
+    /// missing.
+    #[must_use]
+    pub fn omissions(&self) -> usize {
+        self.omissions
+    }
+
+    /// The chord symbol, spelling the root with sharps.
+    #[must_use]
+    pub fn symbol(&self) -> String {
+        format!(
+            "{}{}",
+            pitch::SpelledNotePitchClass::from(self.root),
+            self.quality.suffix()
+        )
+    }
+
+    /// The chord symbol, spelling the root as it would be written in `key`.
+    #[must_use]
+    pub fn symbol_in_key(&self, key: &key::Key) -> String {
+        format!("{}{}", key.spell(self.root), self.quality.suffix())
+    }
+
+    fn rank(&self) -> (usize, std::cmp::Reverse<u8>, i32) {
+        (
+            self.omissions,
+            std::cmp::Reverse(self.quality.commonness()),
+            self.root as i32,
+        )
+    }
+}
+
+/// A reading of a voiced chord: a candidate together with the note in the bass.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordIdentification {
+    candidate: ChordCandidate,
+    bass: pitch::NotePitchClass,
+}
+
+impl ChordIdentification {
+    #[must_use]
+    pub fn candidate(&self) -> &ChordCandidate {
+        &self.candidate
+    }
+
+    #[must_use]
+    pub fn root(&self) -> pitch::NotePitchClass {
+        self.candidate.root
+    }
+
+    #[must_use]
+    pub fn quality(&self) -> ChordQuality {
+        self.candidate.quality
+    }
+
+    #[must_use]
+    pub fn bass(&self) -> pitch::NotePitchClass {
+        self.bass
+    }
+
+    /// The inversion, or `None` when the bass is an added tone or extension.
+    #[must_use]
+    pub fn inversion(&self) -> Option<Inversion> {
+        self.candidate
+            .quality
+            .inversion_for_bass(self.bass as i32 - self.candidate.root as i32)
+    }
+
+    /// The chord symbol with a slash bass when the root is not in the bass, e.g. `Cmaj7/E`.
+    #[must_use]
+    pub fn symbol(&self) -> String {
+        self.slash_symbol(
+            self.candidate.symbol(),
+            pitch::SpelledNotePitchClass::from(self.bass),
+        )
+    }
+
+    /// Like [`ChordIdentification::symbol`], spelling the root and bass as they would be written
+    /// in `key`.
+    #[must_use]
+    pub fn symbol_in_key(&self, key: &key::Key) -> String {
+        self.slash_symbol(self.candidate.symbol_in_key(key), key.spell(self.bass))
+    }
+
+    fn slash_symbol(&self, symbol: String, bass: pitch::SpelledNotePitchClass) -> String {
+        if self.bass == self.candidate.root {
+            symbol
+        } else {
+            format!("{symbol}/{bass}")
+        }
+    }
+}
+
+impl ChordClass {
+    /// Every way of reading the pitch classes as a known chord quality, best first.
+    ///
+    /// Readings that leave out fewer optional tones come first, then more common qualities. For
+    /// example C, E, G and A read as both A minor seventh and C sixth.
+    #[must_use]
+    pub fn identify(&self) -> Vec<ChordCandidate> {
+        let mut candidates: Vec<_> = self
+            .note_pitch_classes
+            .iter()
+            .flat_map(|root| {
+                let semitones = self.semitones_above(*root);
+                ChordQuality::ALL.iter().filter_map(move |quality| {
+                    quality
+                        .omissions(semitones)
+                        .map(|omissions| ChordCandidate {
+                            root: *root,
+                            quality: *quality,
+                            omissions,
+                        })
+                })
+            })
+            .collect();
+        candidates.sort_by_key(ChordCandidate::rank);
+        candidates
+    }
+
+    /// A bit set of the semitones above `root` of every pitch class in the chord.
+    fn semitones_above(&self, root: pitch::NotePitchClass) -> u16 {
+        self.note_pitch_classes.iter().fold(0, |mask, class| {
+            mask | 1 << (*class as i32 - root as i32).rem_euclid(12)
+        })
+    }
+}
+
+impl RootedChordClass {
+    /// The quality of the chord built on its root, if it is a known one.
+    #[must_use]
+    pub fn quality(&self) -> Option<ChordQuality> {
+        let semitones = self.chord_class.semitones_above(self.root);
+        ChordQuality::ALL
+            .iter()
+            .filter_map(|quality| {
+                quality
+                    .omissions(semitones)
+                    .map(|omissions| (omissions, quality))
+            })
+            .min_by_key(|(omissions, quality)| {
+                (*omissions, std::cmp::Reverse(quality.commonness()))
+            })
+            .map(|(_, quality)| *quality)
+    }
+}
+
+impl Chord {
+    /// The pitch classes of the chord, forgetting octaves.
+    #[must_use]
+    pub fn chord_class(&self) -> ChordClass {
+        ChordClass::new(
+            self.note_pitches
+                .iter()
+                .map(pitch::NotePitch::class)
+                .collect(),
+        )
+    }
+
+    /// The lowest pitch of the chord.
+    #[must_use]
+    pub fn bass(&self) -> Option<pitch::NotePitch> {
+        self.note_pitches.first().copied()
+    }
+
+    /// Every reading of the chord, best first. Readings whose root is in the bass are preferred
+    /// over the ranking of [`ChordClass::identify`].
+    #[must_use]
+    pub fn identify(&self) -> Vec<ChordIdentification> {
+        let Some(bass) = self.bass() else {
+            return Vec::new();
+        };
+        let mut identifications: Vec<_> = self
+            .chord_class()
+            .identify()
+            .into_iter()
+            .map(|candidate| ChordIdentification {
+                candidate,
+                bass: bass.class(),
+            })
+            .collect();
+        identifications.sort_by_key(|identification| {
+            (
+                identification.candidate.omissions,
+                identification.candidate.root != identification.bass,
+                identification.inversion().is_none(),
+            )
+        });
+        identifications
+    }
+}
+
+impl ChordQuality {
+    /// Every tone of the chord in full, before any omissions. An altered chord is taken to have
+    /// a flattened ninth and a sharpened fifth.
+    #[must_use]
+    pub fn full_semitones(self) -> Vec<Semitones> {
+        match self {
+            Self::Dominant11 => vec![0, 10, 2, 5, 7],
+            Self::Altered => vec![0, 4, 10, 1, 8],
+            _ => self
+                .required_semitones()
+                .iter()
+                .chain(self.optional_semitones())
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// A change written after the quality of a chord symbol, such as the `#11` of `Bb13#11`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChordAlteration {
+    /// Lowers a degree by a semitone, replacing its natural form.
+    Flat(u8),
+    /// Raises a degree by a semitone, replacing its natural form.
+    Sharp(u8),
+    Add(u8),
+    Omit(u8),
+}
+
+impl ChordAlteration {
+    /// The semitones above the root of the natural form of `degree`, or `None` if chord symbols
+    /// do not use that degree.
+    fn natural_semitones(degree: u8) -> Option<Semitones> {
+        match degree {
+            2 | 9 => Some(2),
+            3 => Some(4),
+            4 | 11 => Some(5),
+            5 => Some(7),
+            6 | 13 => Some(9),
+            7 => Some(10),
+            _ => None,
+        }
+    }
+
+    /// Applies the alteration to a bit set of semitones above the root.
+    fn apply(self, semitones: u16) -> u16 {
+        let (degree, change) = match self {
+            Self::Flat(degree) => (degree, Some(-1)),
+            Self::Sharp(degree) => (degree, Some(1)),
+            Self::Add(degree) => (degree, Some(0)),
+            Self::Omit(degree) => (degree, None),
+        };
+        let Some(natural) = Self::natural_semitones(degree) else {
+            return semitones;
+        };
+        let removed = match (degree, change) {
+            // leaving out the third or fifth leaves it out whatever its quality
+            (3, None) => 0b11 << 3,
+            (5, None) => 0b111 << 6,
+            (_, Some(0)) => 0,
+            _ => 1 << natural,
+        };
+        let added = change.map_or(0, |change| 1 << (natural + change).rem_euclid(12));
+        semitones & !removed | added
+    }
+}
+
+impl Display for ChordAlteration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flat(degree) => write!(f, "b{degree}"),
+            Self::Sharp(degree) => write!(f, "#{degree}"),
+            Self::Add(degree) => write!(f, "add{degree}"),
+            Self::Omit(degree) => write!(f, "no{degree}"),
+        }
+    }
+}
+
+/// A lead-sheet chord symbol such as `F#m7b5`, `Bb13#11` or `C/G`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordSymbol {
+    root: pitch::SpelledNotePitchClass,
+    quality: ChordQuality,
+    alterations: Vec<ChordAlteration>,
+    bass: Option<pitch::SpelledNotePitchClass>,
+}
+
+impl ChordSymbol {
+    #[must_use]
+    pub fn new(
+        root: pitch::SpelledNotePitchClass,
+        quality: ChordQuality,
+        alterations: Vec<ChordAlteration>,
+        bass: Option<pitch::SpelledNotePitchClass>,
+    ) -> Self {
+        Self {
+            root,
+            quality,
+            alterations,
+            bass,
+        }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> pitch::SpelledNotePitchClass {
+        self.root
+    }
+
+    #[must_use]
+    pub fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    #[must_use]
+    pub fn alterations(&self) -> &[ChordAlteration] {
+        &self.alterations
+    }
+
+    /// The note written after the slash, if any.
+    #[must_use]
+    pub fn bass(&self) -> Option<pitch::SpelledNotePitchClass> {
+        self.bass
+    }
+
+    /// The pitch classes the symbol calls for, not counting a slash bass.
+    #[must_use]
+    pub fn rooted_chord_class(&self) -> RootedChordClass {
+        let mask = self
+            .quality
+            .full_semitones()
+            .iter()
+            .fold(0u16, |mask, tone| mask | 1 << tone);
+        let mask = self
+            .alterations
+            .iter()
+            .fold(mask, |mask, alteration| alteration.apply(mask))
+            // no alteration can take away the root
+            | 1;
+        let root = pitch::NotePitchClass::from(self.root);
+        let classes = (0..12)
+            .filter(|semitones| mask & 1 << semitones != 0)
+            .map(|semitones| pitch::NotePitchClass::from_semitones(root as i32 + semitones))
+            .collect();
+        RootedChordClass::new(ChordClass::new(classes), root)
+            .unwrap_or_else(|_| unreachable!("the root is always part of the chord"))
+    }
+}
+
+impl Display for ChordSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.root, self.quality.suffix())?;
+        // without a suffix, an alteration such as b5 would read as part of the root
+        let bracketed = self.quality.suffix().is_empty() && !self.alterations.is_empty();
+        if bracketed {
+            f.write_str("(")?;
+        }
+        for alteration in &self.alterations {
+            write!(f, "{alteration}")?;
+        }
+        if bracketed {
+            f.write_str(")")?;
+        }
+        if let Some(bass) = self.bass {
+            write!(f, "/{bass}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseChordSymbolErrorKind {
+    Empty,
+    InvalidRoot,
+    InvalidAlteration,
+    InvalidDegree,
+    UnclosedParenthesis,
+    UnexpectedCharacter,
+    InvalidBass,
+}
+
+/// Why a chord symbol could not be parsed, and the character, counted from zero, at which the
+/// problem starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParseChordSymbolError {
+    kind: ParseChordSymbolErrorKind,
+    position: usize,
+}
+
+impl ParseChordSymbolError {
+    fn at(kind: ParseChordSymbolErrorKind, text: &str, byte: usize) -> Self {
+        Self {
+            kind,
+            position: text[..byte].chars().count(),
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> ParseChordSymbolErrorKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for ParseChordSymbolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.kind {
+            ParseChordSymbolErrorKind::Empty => "empty chord symbol",
+            ParseChordSymbolErrorKind::InvalidRoot => "invalid chord root",
+            ParseChordSymbolErrorKind::InvalidAlteration => "invalid chord alteration",
+            ParseChordSymbolErrorKind::InvalidDegree => "invalid chord degree",
+            ParseChordSymbolErrorKind::UnclosedParenthesis => "unclosed parenthesis",
+            ParseChordSymbolErrorKind::UnexpectedCharacter => "unexpected character",
+            ParseChordSymbolErrorKind::InvalidBass => "invalid bass note",
+        })?;
+        write!(f, " at character {}", self.position)
+    }
+}
+
+impl Error for ParseChordSymbolError {}
+
+/// Other common ways of writing a quality, besides [`ChordQuality::suffix`].
+const QUALITY_ALIASES: [(&str, ChordQuality); 19] = [
+    ("maj", ChordQuality::Major),
+    ("M", ChordQuality::Major),
+    ("min", ChordQuality::Minor),
+    ("-", ChordQuality::Minor),
+    ("o", ChordQuality::Diminished),
+    ("°", ChordQuality::Diminished),
+    ("+", ChordQuality::Augmented),
+    ("sus", ChordQuality::Suspended4),
+    ("min7", ChordQuality::Minor7),
+    ("-7", ChordQuality::Minor7),
+    ("M7", ChordQuality::Major7),
+    ("Δ", ChordQuality::Major7),
+    ("mM7", ChordQuality::MinorMajor7),
+    ("ø", ChordQuality::HalfDiminished7),
+    ("o7", ChordQuality::Diminished7),
+    ("°7", ChordQuality::Diminished7),
+    ("7sus", ChordQuality::Dominant7Suspended4),
+    ("madd9", ChordQuality::MinorAdd9),
+    ("alt", ChordQuality::Altered),
+];
+
+/// The quality written at the start of `text`, taking the longest name that matches, and the
+/// number of bytes it took up.
+fn parse_quality_prefix(text: &str) -> (ChordQuality, usize) {
+    ChordQuality::ALL
+        .iter()
+        .map(|quality| (quality.suffix(), *quality))
+        .chain(QUALITY_ALIASES)
+        .filter(|(name, _)| text.starts_with(name))
+        .max_by_key(|(name, _)| name.len())
+        .map_or((ChordQuality::Major, 0), |(name, quality)| {
+            (quality, name.len())
+        })
+}
+
+impl FromStr for ChordSymbol {
+    type Err = ParseChordSymbolError;
+
+    /// Parses a root, a quality, any alterations (optionally in parentheses and separated by
+    /// commas) and an optional slash bass. A flat directly after the root letter is read as part
+    /// of the root, so `C` with a flattened fifth must be written `C(b5)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseChordSymbolErrorKind as Kind;
+
+        let (root, mut index) = pitch::SpelledNotePitchClass::parse_prefix(s).map_err(|error| {
+            let kind = match error {
+                pitch::ParseSpelledPitchError::Empty => Kind::Empty,
+                _ => Kind::InvalidRoot,
+            };
+            ParseChordSymbolError::at(kind, s, 0)
+        })?;
+        let (quality, length) = parse_quality_prefix(&s[index..]);
+        index += length;
+
+        let mut alterations = Vec::new();
+        let mut open_parenthesis = None;
+        let mut bass = None;
+        while index < s.len() {
+            let rest = &s[index..];
+            if rest.starts_with('(') && open_parenthesis.is_none() {
+                open_parenthesis = Some(index);
+                index += 1;
+            } else if rest.starts_with(')') && open_parenthesis.is_some() {
+                open_parenthesis = None;
+                index += 1;
+            } else if rest.starts_with(',') && open_parenthesis.is_some() {
+                index += 1;
+            } else if rest.starts_with('/') && open_parenthesis.is_none() {
+                let (class, length) = pitch::SpelledNotePitchClass::parse_prefix(&rest[1..])
+                    .map_err(|_| ParseChordSymbolError::at(Kind::InvalidBass, s, index + 1))?;
+                if 1 + length != rest.len() {
+                    return Err(ParseChordSymbolError::at(
+                        Kind::UnexpectedCharacter,
+                        s,
+                        index + 1 + length,
+                    ));
+                }
+                bass = Some(class);
+                index = s.len();
+            } else {
+                let (alteration, length) = parse_alteration_prefix(s, index)?;
+                alterations.push(alteration);
+                index += length;
+            }
+        }
+        if let Some(open) = open_parenthesis {
+            return Err(ParseChordSymbolError::at(
+                Kind::UnclosedParenthesis,
+                s,
+                open,
+            ));
+        }
+        Ok(Self::new(root, quality, alterations, bass))
+    }
+}
+
+/// Parses the alteration starting at byte `index` of `text`, returning it and the number of
+/// bytes it took up.
+fn parse_alteration_prefix(
+    text: &str,
+    index: usize,
+) -> Result<(ChordAlteration, usize), ParseChordSymbolError> {
+    type Alter = fn(u8) -> ChordAlteration;
+    const PREFIXES: [(&str, Alter); 5] = [
+        ("b", ChordAlteration::Flat),
+        ("#", ChordAlteration::Sharp),
+        ("add", ChordAlteration::Add),
+        ("omit", ChordAlteration::Omit),
+        ("no", ChordAlteration::Omit),
+    ];
+
+    let rest = &text[index..];
+    let (prefix, alteration) = PREFIXES
+        .iter()
+        .find(|(prefix, _)| rest.starts_with(prefix))
+        .ok_or_else(|| {
+            let kind = if rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+                ParseChordSymbolErrorKind::InvalidAlteration
+            } else {
+                ParseChordSymbolErrorKind::UnexpectedCharacter
+            };
+            ParseChordSymbolError::at(kind, text, index)
+        })?;
+    let digits = rest[prefix.len()..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len() - prefix.len());
+    let degree_start = index + prefix.len();
+    let degree = text[degree_start..degree_start + digits]
+        .parse()
+        .ok()
+        .filter(|degree| ChordAlteration::natural_semitones(*degree).is_some())
+        .ok_or_else(|| {
+            ParseChordSymbolError::at(ParseChordSymbolErrorKind::InvalidDegree, text, degree_start)
+        })?;
+    Ok((alteration(degree), prefix.len() + digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::{NotePitch, NotePitchClass, SpelledNotePitchClass};
+
+    use super::*;
+
+    fn chord_class(classes: &[NotePitchClass]) -> ChordClass {
+        ChordClass::new(classes.iter().copied().collect())
+    }
+
+    fn chord(note_pitches: &[(NotePitchClass, i32)]) -> Chord {
+        Chord::new(
+            note_pitches
+                .iter()
+                .map(|(class, octave)| NotePitch::new(*class, *octave))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn identify_triads_and_sevenths() {
+        use NotePitchClass::{As, Fs, A, B, C, D, E, G};
+
+        let best = |classes: &[NotePitchClass]| chord_class(classes).identify()[0].symbol();
+        assert_eq!(best(&[C, E, G]), "C");
+        assert_eq!(best(&[A, C, E]), "Am");
+        assert_eq!(best(&[B, D, NotePitchClass::F]), "Bdim");
+        assert_eq!(best(&[G, B, D, NotePitchClass::F]), "G7");
+        assert_eq!(best(&[C, E, G, B]), "Cmaj7");
+        assert_eq!(best(&[B, D, NotePitchClass::F, A]), "Bm7b5");
+        assert_eq!(best(&[C, E, As, D]), "C9");
+        assert_eq!(best(&[D, Fs, A, E]), "Dadd9");
+        assert_eq!(
+            best(&[G, B, NotePitchClass::F, NotePitchClass::Gs]),
+            "G7alt"
+        );
+        // a seventh chord without its fifth
+        assert_eq!(best(&[G, B, NotePitchClass::F]), "G7");
+    }
+
+    #[test]
+    fn ambiguous_sets_are_ranked() {
+        use NotePitchClass::{A, C, E, G};
+
+        let candidates = chord_class(&[C, E, G, A]).identify();
+        let symbols: Vec<_> = candidates.iter().map(ChordCandidate::symbol).collect();
+        assert!(symbols.contains(&"Am7".to_owned()));
+        assert!(symbols.contains(&"C6".to_owned()));
+
+        let c6 = chord(&[(C, 3), (E, 3), (G, 3), (A, 3)]).identify();
+        assert_eq!(c6[0].symbol(), "C6");
+        assert_eq!(c6[0].inversion(), Some(Inversion::Root));
+
+        let am7 = chord(&[(A, 2), (C, 3), (E, 3), (G, 3)]).identify();
+        assert_eq!(am7[0].symbol(), "Am7");
+    }
+
+    #[test]
+    fn inversions_and_slash_symbols() {
+        use NotePitchClass::{B, C, E, G};
+
+        let first_inversion = chord(&[(E, 3), (G, 3), (B, 3), (C, 4)]).identify();
+        assert_eq!(first_inversion[0].symbol(), "Cmaj7/E");
+        assert_eq!(first_inversion[0].inversion(), Some(Inversion::First));
+
+        let second_inversion = chord(&[(G, 3), (C, 4), (E, 4)]).identify();
+        assert_eq!(second_inversion[0].symbol(), "C/G");
+        assert_eq!(second_inversion[0].inversion(), Some(Inversion::Second));
+
+        let flat_key = key::Key::major(SpelledNotePitchClass::from_fifths(-2).unwrap()).unwrap();
+        let b_flat = chord(&[
+            (NotePitchClass::D, 3),
+            (NotePitchClass::F, 3),
+            (NotePitchClass::As, 3),
+        ])
+        .identify();
+        assert_eq!(b_flat[0].symbol(), "A#/D");
+        assert_eq!(b_flat[0].symbol_in_key(&flat_key), "Bb/D");
+    }
+
+    #[test]
+    fn rooted_chord_class_quality() {
+        use NotePitchClass::{A, C, E, G};
+
+        let rooted = RootedChordClass::new(chord_class(&[C, E, G, A]), C).unwrap();
+        assert_eq!(rooted.quality(), Some(ChordQuality::Major6));
+        let unknown = RootedChordClass::new(chord_class(&[C, NotePitchClass::Cs]), C).unwrap();
+        assert_eq!(unknown.quality(), None);
+    }
+
+    fn classes(symbol: &str) -> Vec<NotePitchClass> {
+        let rooted = symbol.parse::<ChordSymbol>().unwrap().rooted_chord_class();
+        let mut classes: Vec<_> = rooted
+            .chord_class()
+            .note_pitch_classes()
+            .iter()
+            .copied()
+            .collect();
+        classes.sort_by_key(|class| (*class as i32 - rooted.root() as i32).rem_euclid(12));
+        classes
+    }
+
+    #[test]
+    fn parse_chord_symbols() {
+        use NotePitchClass::{As, Cs, Ds, Fs, Gs, A, B, C, D, E, F, G};
+
+        assert_eq!(classes("F#m7b5"), vec![Fs, A, C, E]);
+        assert_eq!(classes("Bb13#11"), vec![As, C, D, E, F, G, Gs]);
+        assert_eq!(classes("Dsus4"), vec![D, G, A]);
+        assert_eq!(classes("Ebmaj9"), vec![Ds, F, G, As, D]);
+        assert_eq!(classes("G7alt"), vec![G, Gs, B, Ds, F]);
+        assert_eq!(classes("C7(b9,#9)"), vec![C, Cs, Ds, E, G, As]);
+        assert_eq!(classes("Am7no5"), vec![A, C, G]);
+
+        let slash: ChordSymbol = "C/G".parse().unwrap();
+        assert_eq!(slash.quality(), ChordQuality::Major);
+        assert_eq!(slash.bass(), Some("G".parse().unwrap()));
+        assert_eq!(slash.rooted_chord_class().root(), C);
+
+        let bb: ChordSymbol = "Bb13#11".parse().unwrap();
+        assert_eq!(bb.root(), "Bb".parse().unwrap());
+        assert_eq!(bb.quality(), ChordQuality::Dominant13);
+        assert_eq!(bb.alterations(), &[ChordAlteration::Sharp(11)]);
+        assert_eq!(
+            "CΔ".parse::<ChordSymbol>().unwrap().quality(),
+            ChordQuality::Major7
+        );
+    }
+
+    #[test]
+    fn chord_symbols_round_trip() {
+        for text in [
+            "F#m7b5", "Bb13#11", "C/G", "Dsus4", "Ebmaj9", "G7alt", "C(b5)", "Am7b9/G", "Cm(maj7)",
+            "E7#9", "Fadd9no3",
+        ] {
+            let symbol: ChordSymbol = text.parse().unwrap();
+            assert_eq!(symbol.to_string(), text);
+            assert_eq!(symbol.to_string().parse::<ChordSymbol>(), Ok(symbol));
+        }
+        assert_eq!("Cmin7".parse::<ChordSymbol>().unwrap().to_string(), "Cm7");
+    }
+
+    #[test]
+    fn chord_symbol_errors_point_at_the_problem() {
+        let error = |text: &str| {
+            let error = text.parse::<ChordSymbol>().unwrap_err();
+            (error.kind(), error.position())
+        };
+        assert_eq!(error(""), (ParseChordSymbolErrorKind::Empty, 0));
+        assert_eq!(error("H7"), (ParseChordSymbolErrorKind::InvalidRoot, 0));
+        assert_eq!(
+            error("C7x9"),
+            (ParseChordSymbolErrorKind::InvalidAlteration, 2)
+        );
+        assert_eq!(error("C7#8"), (ParseChordSymbolErrorKind::InvalidDegree, 3));
+        assert_eq!(
+            error("C7(b9"),
+            (ParseChordSymbolErrorKind::UnclosedParenthesis, 2)
+        );
+        assert_eq!(error("C/Q"), (ParseChordSymbolErrorKind::InvalidBass, 2));
+        assert_eq!(
+            error("C/Gm"),
+            (ParseChordSymbolErrorKind::UnexpectedCharacter, 3)
+        );
+        assert_eq!(
+            error("CΔ?"),
+            (ParseChordSymbolErrorKind::UnexpectedCharacter, 2)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::{error::Error, fmt::Display, ops::Add, str::FromStr};
+
 use crate::pitch;
 
 #[cfg(feature = "serde")]
@@ -51,30 +53,406 @@ impl SemitoneInterval {
 
     #[must_use]
     pub fn apply_to_note_pitch(&self, note_pitch: &pitch::NotePitch) -> pitch::NotePitch {
-        let total_semitones = self.semitones();
-
-        // get octaves and semitones through division
-        let octaves = total_semitones / 12;
-        let semitones = total_semitones % 12;
+        let absolute_semitones = note_pitch.semitones() + self.semitones();
 
-        let new_base_octave = note_pitch.octave() + octaves;
-        let new_pitch_class_without_modulo = note_pitch.class() as i32 + semitones;
-
-        let fixed_octave = new_base_octave + (new_pitch_class_without_modulo / 12);
-        let fixed_pitch_class =
-            pitch::NotePitchClass::try_from(new_pitch_class_without_modulo % 12).unwrap();
+        let fixed_octave = absolute_semitones.div_euclid(12);
+        let fixed_pitch_class = pitch::NotePitchClass::from_semitones(absolute_semitones);
 
         pitch::NotePitch::new(fixed_pitch_class, fixed_octave)
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Direction {
     Up,
     Down,
 }
 
+/// The quality of a [`DiatonicInterval`]. Augmented and diminished qualities carry how many times
+/// they are applied, so `Augmented(2)` is doubly augmented.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IntervalQuality {
+    Perfect,
+    Major,
+    Minor,
+    Augmented(u8),
+    Diminished(u8),
+}
+
+impl IntervalQuality {
+    /// The quality an interval gets when it is inverted.
+    #[must_use]
+    pub fn inverted(self) -> Self {
+        match self {
+            Self::Perfect => Self::Perfect,
+            Self::Major => Self::Minor,
+            Self::Minor => Self::Major,
+            Self::Augmented(count) => Self::Diminished(count),
+            Self::Diminished(count) => Self::Augmented(count),
+        }
+    }
+}
+
+impl Display for IntervalQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Perfect => f.write_str("P"),
+            Self::Major => f.write_str("M"),
+            Self::Minor => f.write_str("m"),
+            Self::Augmented(count) => f.write_str(&"A".repeat(usize::from(*count))),
+            Self::Diminished(count) => f.write_str(&"d".repeat(usize::from(*count))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewDiatonicIntervalError {
+    /// Interval numbers start at 1 for a unison.
+    ZeroNumber,
+    /// Only unisons, fourths, fifths and their compounds can be perfect, and only they cannot be
+    /// major or minor.
+    QualityDoesNotMatchNumber,
+    /// Augmented and diminished qualities must be applied at least once.
+    ZeroAugmentedOrDiminished,
+    /// The number is above [`DiatonicInterval::MAX_NUMBER`].
+    NumberTooLarge,
+}
+
+impl Display for NewDiatonicIntervalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ZeroNumber => "interval number must be at least 1",
+            Self::QualityDoesNotMatchNumber => "quality is not possible for the interval number",
+            Self::ZeroAugmentedOrDiminished => "augmented or diminished count must be at least 1",
+            Self::NumberTooLarge => "interval number is too large",
+        })
+    }
+}
+
+impl Error for NewDiatonicIntervalError {}
+
+/// An interval named by quality and number, such as a major third or a diminished fifth.
+///
+/// Unlike [`SemitoneInterval`], this tells enharmonically equivalent intervals apart: an augmented
+/// fourth and a diminished fifth both span six semitones but are different values.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct DiatonicInterval {
+    quality: IntervalQuality,
+    number: u32,
+    direction: Direction,
+}
+
+impl DiatonicInterval {
+    /// The largest interval number, spanning over 9000 octaves, which keeps the semitones of an
+    /// interval and of the sum of two intervals well within [`Semitones`].
+    pub const MAX_NUMBER: u32 = 65_535;
+
+    /// Creates an interval; `number` is 1 for a unison, 8 for an octave, 10 for a compound third
+    /// and so on.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NewDiatonicIntervalError`] if the number is zero, too large or cannot take the
+    /// quality.
+    pub fn new(
+        quality: IntervalQuality,
+        number: u32,
+        direction: Direction,
+    ) -> Result<Self, NewDiatonicIntervalError> {
+        if number == 0 {
+            return Err(NewDiatonicIntervalError::ZeroNumber);
+        }
+        if number > Self::MAX_NUMBER {
+            return Err(NewDiatonicIntervalError::NumberTooLarge);
+        }
+        let perfect_number = matches!((number - 1) % 7, 0 | 3 | 4);
+        match quality {
+            IntervalQuality::Perfect if !perfect_number => {
+                return Err(NewDiatonicIntervalError::QualityDoesNotMatchNumber)
+            }
+            IntervalQuality::Major | IntervalQuality::Minor if perfect_number => {
+                return Err(NewDiatonicIntervalError::QualityDoesNotMatchNumber)
+            }
+            IntervalQuality::Augmented(0) | IntervalQuality::Diminished(0) => {
+                return Err(NewDiatonicIntervalError::ZeroAugmentedOrDiminished)
+            }
+            _ => {}
+        }
+        // a perfect unison has no direction; keep it in one form so that equality works
+        let direction = if number == 1 && quality == IntervalQuality::Perfect {
+            Direction::Up
+        } else {
+            direction
+        };
+        Ok(Self {
+            quality,
+            number,
+            direction,
+        })
+    }
+
+    /// The interval from `from` to `to`, descending if `to` is written lower than `from`, or
+    /// `None` if the pitches are so far apart that its number would be above
+    /// [`DiatonicInterval::MAX_NUMBER`].
+    #[must_use]
+    pub fn new_from_spelled_note_pitches(
+        from: &pitch::SpelledNotePitch,
+        to: &pitch::SpelledNotePitch,
+    ) -> Option<Self> {
+        // double accidentals can never augment an interval 255 times, so only the number can be
+        // out of range
+        Self::from_steps_and_semitones(
+            to.diatonic_steps().checked_sub(from.diatonic_steps())?,
+            to.semitones().checked_sub(from.semitones())?,
+        )
+    }
+
+    /// The interval that moves by `steps` letter names and `semitones` semitones, or `None` if
+    /// the quality would be more than 255-fold augmented or diminished or the number would be
+    /// above [`DiatonicInterval::MAX_NUMBER`].
+    #[must_use]
+    pub fn from_steps_and_semitones(steps: i32, semitones: Semitones) -> Option<Self> {
+        let descending = steps < 0 || (steps == 0 && semitones < 0);
+        let (semitones, direction) = if descending {
+            (-i64::from(semitones), Direction::Down)
+        } else {
+            (i64::from(semitones), Direction::Up)
+        };
+        let steps = steps.unsigned_abs();
+        if steps >= Self::MAX_NUMBER {
+            return None;
+        }
+
+        let difference = semitones - i64::from(major_or_perfect_semitones(steps));
+        let count = |difference: i64| u8::try_from(difference).ok();
+        let quality = if matches!(steps % 7, 0 | 3 | 4) {
+            match difference {
+                0 => IntervalQuality::Perfect,
+                d if d > 0 => IntervalQuality::Augmented(count(d)?),
+                d => IntervalQuality::Diminished(count(-d)?),
+            }
+        } else {
+            match difference {
+                0 => IntervalQuality::Major,
+                -1 => IntervalQuality::Minor,
+                d if d > 0 => IntervalQuality::Augmented(count(d)?),
+                d => IntervalQuality::Diminished(count(-d - 1)?),
+            }
+        };
+
+        Self::new(quality, steps + 1, direction).ok()
+    }
+
+    #[must_use]
+    pub fn quality(&self) -> IntervalQuality {
+        self.quality
+    }
+
+    #[must_use]
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    #[must_use]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Letter names moved by the interval, negative when descending.
+    #[must_use]
+    pub fn steps(&self) -> i32 {
+        let steps = i32::try_from(self.number - 1).unwrap_or(i32::MAX);
+        match self.direction {
+            Direction::Up => steps,
+            Direction::Down => -steps,
+        }
+    }
+
+    #[must_use]
+    pub fn semitone_interval(&self) -> SemitoneInterval {
+        let steps = self.number - 1;
+        let semitones = major_or_perfect_semitones(steps)
+            + match self.quality {
+                IntervalQuality::Perfect | IntervalQuality::Major => 0,
+                IntervalQuality::Minor => -1,
+                IntervalQuality::Augmented(count) => i32::from(count),
+                IntervalQuality::Diminished(count) if matches!(steps % 7, 0 | 3 | 4) => {
+                    -i32::from(count)
+                }
+                IntervalQuality::Diminished(count) => -i32::from(count) - 1,
+            };
+        SemitoneInterval::new_from_direction(semitones, self.direction)
+    }
+
+    #[must_use]
+    pub fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    /// The interval reduced to within an octave, keeping octaves as octaves.
+    #[must_use]
+    pub fn simple(&self) -> Self {
+        let number = if self.number > 8 {
+            (self.number - 2) % 7 + 2
+        } else {
+            self.number
+        };
+        Self { number, ..*self }
+    }
+
+    /// The interval that completes this one to an octave, e.g. a major third inverts to a minor
+    /// sixth. Compound intervals are reduced first.
+    #[must_use]
+    pub fn inverted(&self) -> Self {
+        // perfect numbers invert to perfect numbers, so the quality stays valid
+        Self {
+            quality: self.quality.inverted(),
+            number: 9 - self.simple().number,
+            direction: self.direction,
+        }
+    }
+
+    /// The same interval in the opposite direction.
+    #[must_use]
+    pub fn reversed(&self) -> Self {
+        if self.number == 1 && self.quality == IntervalQuality::Perfect {
+            return *self;
+        }
+        Self {
+            direction: match self.direction {
+                Direction::Up => Direction::Down,
+                Direction::Down => Direction::Up,
+            },
+            ..*self
+        }
+    }
+
+    /// Adds two intervals, returning `None` if the result's quality cannot be represented.
+    #[must_use]
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Self::from_steps_and_semitones(
+            self.steps() + other.steps(),
+            self.semitone_interval().semitones() + other.semitone_interval().semitones(),
+        )
+    }
+
+    /// Moves `note_pitch` by the interval, spelling the result with the letter the interval's
+    /// number calls for. Returns `None` if that would need more than a double accidental.
+    #[must_use]
+    pub fn apply_to_spelled_note_pitch(
+        &self,
+        note_pitch: &pitch::SpelledNotePitch,
+    ) -> Option<pitch::SpelledNotePitch> {
+        pitch::SpelledNotePitch::from_steps_and_semitones(
+            note_pitch.diatonic_steps() + self.steps(),
+            note_pitch.semitones() + self.semitone_interval().semitones(),
+        )
+    }
+}
+
+impl Add for DiatonicInterval {
+    type Output = Self;
+
+    /// # Panics
+    ///
+    /// Panics if the sum is more than 255-fold augmented or diminished or its number is above
+    /// [`DiatonicInterval::MAX_NUMBER`]; use [`DiatonicInterval::checked_add`] to handle those
+    /// cases.
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(&rhs).unwrap_or_else(|| {
+            panic!("interval sum is too augmented, too diminished or too large to represent")
+        })
+    }
+}
+
+impl From<DiatonicInterval> for SemitoneInterval {
+    fn from(interval: DiatonicInterval) -> Self {
+        interval.semitone_interval()
+    }
+}
+
+impl Display for DiatonicInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.direction == Direction::Down {
+            f.write_str("-")?;
+        }
+        write!(f, "{}{}", self.quality, self.number)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseDiatonicIntervalError {
+    InvalidQuality,
+    InvalidNumber,
+    Invalid(NewDiatonicIntervalError),
+}
+
+impl Display for ParseDiatonicIntervalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidQuality => f.write_str("invalid interval quality"),
+            Self::InvalidNumber => f.write_str("invalid interval number"),
+            Self::Invalid(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for ParseDiatonicIntervalError {}
+
+/// Parses the notation produced by [`Display`], such as `M3`, `P5`, `AA4` or `-m6`.
+impl FromStr for DiatonicInterval {
+    type Err = ParseDiatonicIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (direction, s) = match s.strip_prefix('-') {
+            Some(rest) => (Direction::Down, rest),
+            None => (Direction::Up, s),
+        };
+        let quality_length = s
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or(ParseDiatonicIntervalError::InvalidNumber)?;
+        let (quality, number) = s.split_at(quality_length);
+        let repeated = |letter: char| {
+            (!quality.is_empty() && quality.chars().all(|c| c == letter))
+                .then(|| u8::try_from(quality.len()).ok())
+                .flatten()
+        };
+        let quality = match quality {
+            "P" => IntervalQuality::Perfect,
+            "M" => IntervalQuality::Major,
+            "m" => IntervalQuality::Minor,
+            _ => {
+                if let Some(count) = repeated('A') {
+                    IntervalQuality::Augmented(count)
+                } else if let Some(count) = repeated('d') {
+                    IntervalQuality::Diminished(count)
+                } else {
+                    return Err(ParseDiatonicIntervalError::InvalidQuality);
+                }
+            }
+        };
+        let number = number
+            .parse()
+            .map_err(|_| ParseDiatonicIntervalError::InvalidNumber)?;
+        Self::new(quality, number, direction).map_err(ParseDiatonicIntervalError::Invalid)
+    }
+}
+
+/// Semitones in the major or perfect interval spanning `steps` diatonic steps, which must be
+/// below [`DiatonicInterval::MAX_NUMBER`].
+fn major_or_perfect_semitones(steps: u32) -> Semitones {
+    const WITHIN_OCTAVE: [Semitones; 7] = [0, 2, 4, 5, 7, 9, 11];
+    let octaves = Semitones::try_from(steps / 7)
+        .unwrap_or_else(|_| unreachable!("interval numbers are far below Semitones::MAX"));
+    WITHIN_OCTAVE[(steps % 7) as usize] + 12 * octaves
+}
+
 #[cfg(test)]
 mod tests {
     use crate::pitch::NotePitch;
@@ -167,4 +545,139 @@ mod tests {
         assert_eq!(interval3.apply_to_note_pitch(&note2), note1);
         assert_eq!(interval4.apply_to_note_pitch(&note3), note1);
     }
+
+    #[test]
+    fn apply_interval_to_note_pitch_crossing_octave_downwards() {
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        let b3 = NotePitch::new(NotePitchClass::B, 3);
+        let a2 = NotePitch::new(NotePitchClass::A, 2);
+
+        assert_eq!(SemitoneInterval::new(-1).apply_to_note_pitch(&c4), b3);
+        assert_eq!(SemitoneInterval::new(-15).apply_to_note_pitch(&c4), a2);
+        assert_eq!(SemitoneInterval::new(1).apply_to_note_pitch(&b3), c4);
+    }
+
+    fn spelled(name: &str) -> pitch::SpelledNotePitch {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn diatonic_interval_from_spelled_note_pitches() {
+        let augmented_fourth =
+            DiatonicInterval::new_from_spelled_note_pitches(&spelled("C4"), &spelled("F#4"))
+                .unwrap();
+        let diminished_fifth =
+            DiatonicInterval::new_from_spelled_note_pitches(&spelled("C4"), &spelled("Gb4"))
+                .unwrap();
+        assert_eq!(augmented_fourth.to_string(), "A4");
+        assert_eq!(diminished_fifth.to_string(), "d5");
+        assert_ne!(augmented_fourth, diminished_fifth);
+        assert_eq!(
+            augmented_fourth.semitone_interval(),
+            diminished_fifth.semitone_interval()
+        );
+
+        let cases = [
+            ("C4", "E4", "M3"),
+            ("E4", "C5", "m6"),
+            ("B3", "F4", "d5"),
+            ("C4", "D5", "M9"),
+            ("Eb4", "C#5", "A6"),
+            ("C#4", "Eb4", "d3"),
+            ("G4", "C4", "-P5"),
+            ("C4", "Cb4", "-A1"),
+            ("B#3", "Cb4", "dd2"),
+            ("C4", "C6", "P15"),
+        ];
+        for (from, to, expected) in cases {
+            let interval =
+                DiatonicInterval::new_from_spelled_note_pitches(&spelled(from), &spelled(to))
+                    .unwrap();
+            assert_eq!(interval.to_string(), expected, "{from} to {to}");
+            assert_eq!(expected.parse::<DiatonicInterval>(), Ok(interval));
+            assert_eq!(
+                interval.semitone_interval(),
+                SemitoneInterval::new_from_note_pitches(
+                    &spelled(from).note_pitch(),
+                    &spelled(to).note_pitch()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn diatonic_interval_inversion_and_addition() {
+        let parse = |text: &str| text.parse::<DiatonicInterval>().unwrap();
+
+        assert_eq!(parse("M3").inverted(), parse("m6"));
+        assert_eq!(parse("A4").inverted(), parse("d5"));
+        assert_eq!(parse("P1").inverted(), parse("P8"));
+        assert_eq!(parse("P8").inverted(), parse("P1"));
+        assert_eq!(parse("M10").inverted(), parse("m6"));
+
+        assert_eq!(parse("M3") + parse("m3"), parse("P5"));
+        assert_eq!(parse("M3") + parse("M3"), parse("A5"));
+        assert_eq!(parse("P5") + parse("P5"), parse("M9"));
+        assert_eq!(parse("M3") + parse("-M3"), parse("P1"));
+        assert_eq!(parse("m3").reversed(), parse("-m3"));
+
+        assert_eq!("m3".parse::<DiatonicInterval>().map(|i| i.number()), Ok(3));
+        assert_eq!(
+            "P3".parse::<DiatonicInterval>(),
+            Err(ParseDiatonicIntervalError::Invalid(
+                NewDiatonicIntervalError::QualityDoesNotMatchNumber
+            ))
+        );
+        assert_eq!(
+            "A4294967295".parse::<DiatonicInterval>(),
+            Err(ParseDiatonicIntervalError::Invalid(
+                NewDiatonicIntervalError::NumberTooLarge
+            ))
+        );
+        assert_eq!(
+            DiatonicInterval::from_steps_and_semitones(i32::MIN, i32::MIN),
+            None
+        );
+        assert_eq!(
+            DiatonicInterval::new_from_spelled_note_pitches(&spelled("C0"), &spelled("C10000")),
+            None
+        );
+        let widest = DiatonicInterval::new(
+            IntervalQuality::Perfect,
+            DiatonicInterval::MAX_NUMBER,
+            Direction::Up,
+        )
+        .unwrap();
+        assert_eq!(widest.checked_add(&parse("P8")), None);
+    }
+
+    #[test]
+    fn apply_diatonic_interval_to_spelled_note_pitch() {
+        let parse = |text: &str| text.parse::<DiatonicInterval>().unwrap();
+
+        assert_eq!(
+            parse("M3").apply_to_spelled_note_pitch(&spelled("D4")),
+            Some(spelled("F#4"))
+        );
+        assert_eq!(
+            parse("m3").apply_to_spelled_note_pitch(&spelled("A4")),
+            Some(spelled("C5"))
+        );
+        assert_eq!(
+            parse("d7").apply_to_spelled_note_pitch(&spelled("C#4")),
+            Some(spelled("Bb4"))
+        );
+        assert_eq!(
+            parse("-m2").apply_to_spelled_note_pitch(&spelled("C4")),
+            Some(spelled("B3"))
+        );
+        assert_eq!(
+            parse("A1").apply_to_spelled_note_pitch(&spelled("B3")),
+            Some(spelled("B#3"))
+        );
+        assert_eq!(
+            parse("AA4").apply_to_spelled_note_pitch(&spelled("F##4")),
+            None
+        );
+    }
 }
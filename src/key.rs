@@ -0,0 +1,428 @@
+use std::{collections::HashSet, error::Error, fmt::Display};
+
+use crate::{
+    harmony,
+    pitch::{self, Accidental, Letter, SpelledNotePitchClass},
+    scale::{Mode, Scale},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The sharps or flats written at the start of a staff, stored as a position on the circle of
+/// fifths: positive for sharps, negative for flats.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct KeySignature {
+    fifths: i32,
+}
+
+impl KeySignature {
+    #[must_use]
+    pub fn new(fifths: i32) -> Self {
+        Self { fifths }
+    }
+
+    #[must_use]
+    pub fn fifths(&self) -> i32 {
+        self.fifths
+    }
+
+    #[must_use]
+    pub fn sharps(&self) -> u32 {
+        self.fifths.max(0).unsigned_abs()
+    }
+
+    #[must_use]
+    pub fn flats(&self) -> u32 {
+        self.fifths.min(0).unsigned_abs()
+    }
+
+    /// The altered pitch classes in the order they are written, e.g. F#, C#, G# for three sharps.
+    /// Signatures beyond seven sharps or flats continue with double accidentals.
+    #[must_use]
+    pub fn accidentals(&self) -> Vec<SpelledNotePitchClass> {
+        let positions: Vec<i32> = if self.fifths >= 0 {
+            (6..6 + self.fifths).collect()
+        } else {
+            (0..-self.fifths).map(|index| -2 - index).collect()
+        };
+        positions
+            .into_iter()
+            .filter_map(SpelledNotePitchClass::from_fifths)
+            .collect()
+    }
+
+    /// The accidental the signature applies to `letter`, or `None` if it would need more than a
+    /// double accidental.
+    #[must_use]
+    pub fn accidental_for(&self, letter: Letter) -> Option<Accidental> {
+        let letter_fifths = SpelledNotePitchClass::natural(letter).fifths();
+        // the first sharp is written at F# (6 fifths), one fifth above the letter F (-1); every
+        // seven further fifths alter the letter once more, and flats work the same way downwards
+        Accidental::from_semitones((self.fifths + 5 - letter_fifths).div_euclid(7))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewKeyError {
+    /// The key would need a key signature with triple sharps or flats.
+    SignatureTooRemote,
+}
+
+impl Display for NewKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the key signature would need more than double accidentals")
+    }
+}
+
+impl Error for NewKeyError {}
+
+/// A tonic together with a mode, such as E-flat major or D dorian.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Key {
+    tonic: SpelledNotePitchClass,
+    mode: Mode,
+}
+
+impl Key {
+    /// # Errors
+    ///
+    /// Returns [`NewKeyError::SignatureTooRemote`] for keys like B-double-sharp major whose
+    /// signature would need triple accidentals.
+    pub fn new(tonic: SpelledNotePitchClass, mode: Mode) -> Result<Self, NewKeyError> {
+        let key = Self { tonic, mode };
+        if key.signature().fifths().abs() > 14 {
+            return Err(NewKeyError::SignatureTooRemote);
+        }
+        Ok(key)
+    }
+
+    /// # Errors
+    ///
+    /// See [`Key::new`].
+    pub fn major(tonic: SpelledNotePitchClass) -> Result<Self, NewKeyError> {
+        Self::new(tonic, Mode::Ionian)
+    }
+
+    /// A natural minor key.
+    ///
+    /// # Errors
+    ///
+    /// See [`Key::new`].
+    pub fn minor(tonic: SpelledNotePitchClass) -> Result<Self, NewKeyError> {
+        Self::new(tonic, Mode::Aeolian)
+    }
+
+    #[must_use]
+    pub fn tonic(&self) -> SpelledNotePitchClass {
+        self.tonic
+    }
+
+    #[must_use]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    #[must_use]
+    pub fn signature(&self) -> KeySignature {
+        KeySignature::new(self.tonic.fifths() + mode_fifths(self.mode))
+    }
+
+    #[must_use]
+    pub fn scale(&self) -> Scale {
+        Scale::new(self.tonic.note_pitch_class(), self.mode.pattern())
+    }
+
+    /// The seven degrees of the key spelled with consecutive letters, starting on the tonic.
+    #[must_use]
+    pub fn spelled_degrees(&self) -> Vec<SpelledNotePitchClass> {
+        let signature = self.signature();
+        (0..7)
+            .map(|step| {
+                let letter = Letter::from_steps(self.tonic.letter().steps() + step);
+                let accidental = signature
+                    .accidental_for(letter)
+                    .unwrap_or_else(|| unreachable!("keys are limited to double accidentals"));
+                SpelledNotePitchClass::new(letter, accidental)
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn contains(&self, class: pitch::NotePitchClass) -> bool {
+        self.scale().contains(class)
+    }
+
+    /// Spells `class` as it would normally be written in this key: diatonic pitch classes take
+    /// their scale spelling and chromatic ones the spelling closest to the key on the circle of
+    /// fifths.
+    #[must_use]
+    pub fn spell(&self, class: pitch::NotePitchClass) -> SpelledNotePitchClass {
+        if let Some(spelled) = self
+            .spelled_degrees()
+            .into_iter()
+            .find(|spelled| spelled.note_pitch_class() == class)
+        {
+            return spelled;
+        }
+        // halfway between the centre of the signature and the tonic, doubled to stay integral,
+        // so that minor keys still prefer their raised sixth and seventh degrees
+        let centre = self.signature().fifths() + self.tonic.fifths() + 4;
+        SpelledNotePitchClass::spellings(class)
+            .into_iter()
+            .min_by_key(|spelled| {
+                (
+                    (2 * spelled.fifths() - centre).abs(),
+                    spelled.accidental().semitones().abs(),
+                    spelled.fifths(),
+                )
+            })
+            .unwrap_or_else(|| unreachable!("every pitch class has several spellings"))
+    }
+
+    /// The key with the same signature in `mode`, e.g. A minor for C major.
+    #[must_use]
+    pub fn relative(&self, mode: Mode) -> Self {
+        let fifths = self.signature().fifths() - mode_fifths(mode);
+        Self {
+            tonic: SpelledNotePitchClass::from_fifths(fifths).unwrap_or_else(|| {
+                unreachable!(
+                    "signatures of at most 14 fifths have tonics within double accidentals"
+                )
+            }),
+            mode,
+        }
+    }
+
+    /// The relative minor of a major key or the relative major of any other mode.
+    #[must_use]
+    pub fn relative_major_or_minor(&self) -> Self {
+        match self.mode {
+            Mode::Ionian => self.relative(Mode::Aeolian),
+            _ => self.relative(Mode::Ionian),
+        }
+    }
+
+    /// The key with the same tonic in `mode`, e.g. C minor for C major.
+    ///
+    /// # Errors
+    ///
+    /// See [`Key::new`].
+    pub fn parallel(&self, mode: Mode) -> Result<Self, NewKeyError> {
+        Self::new(self.tonic, mode)
+    }
+
+    /// The neighbouring key one fifth higher on the circle of fifths, in the same mode.
+    ///
+    /// # Errors
+    ///
+    /// See [`Key::new`].
+    pub fn dominant_key(&self) -> Result<Self, NewKeyError> {
+        self.moved_on_circle_of_fifths(1)
+    }
+
+    /// The neighbouring key one fifth lower on the circle of fifths, in the same mode.
+    ///
+    /// # Errors
+    ///
+    /// See [`Key::new`].
+    pub fn subdominant_key(&self) -> Result<Self, NewKeyError> {
+        self.moved_on_circle_of_fifths(-1)
+    }
+
+    fn moved_on_circle_of_fifths(self, fifths: i32) -> Result<Self, NewKeyError> {
+        let tonic = SpelledNotePitchClass::from_fifths(self.tonic.fifths() + fifths)
+            .ok_or(NewKeyError::SignatureTooRemote)?;
+        Self::new(tonic, self.mode)
+    }
+
+    /// The triads built on each degree of the key, starting on the tonic.
+    #[must_use]
+    pub fn diatonic_triads(&self) -> Vec<harmony::RootedChordClass> {
+        self.stacked_thirds(3)
+    }
+
+    /// The seventh chords built on each degree of the key, starting on the tonic.
+    #[must_use]
+    pub fn diatonic_seventh_chords(&self) -> Vec<harmony::RootedChordClass> {
+        self.stacked_thirds(4)
+    }
+
+    fn stacked_thirds(self, size: usize) -> Vec<harmony::RootedChordClass> {
+        let degrees = self.scale().degrees();
+        (0..degrees.len())
+            .map(|root| {
+                let classes: HashSet<_> = (0..size)
+                    .map(|third| degrees[(root + 2 * third) % degrees.len()])
+                    .collect();
+                harmony::RootedChordClass::new(harmony::ChordClass::new(classes), degrees[root])
+                    .unwrap_or_else(|_| {
+                        unreachable!("the root is the first pitch class of the stack")
+                    })
+            })
+            .collect()
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match self.mode {
+            Mode::Ionian => "major",
+            Mode::Dorian => "dorian",
+            Mode::Phrygian => "phrygian",
+            Mode::Lydian => "lydian",
+            Mode::Mixolydian => "mixolydian",
+            Mode::Aeolian => "minor",
+            Mode::Locrian => "locrian",
+        };
+        write!(f, "{} {mode}", self.tonic)
+    }
+}
+
+/// How many fifths the signature of `mode` lies from the signature of the major key on the same
+/// tonic.
+fn mode_fifths(mode: Mode) -> i32 {
+    match mode {
+        Mode::Lydian => 1,
+        Mode::Ionian => 0,
+        Mode::Mixolydian => -1,
+        Mode::Dorian => -2,
+        Mode::Aeolian => -3,
+        Mode::Phrygian => -4,
+        Mode::Locrian => -5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::NotePitchClass;
+
+    use super::*;
+
+    fn spelled(name: &str) -> SpelledNotePitchClass {
+        name.parse().unwrap()
+    }
+
+    fn names(classes: &[SpelledNotePitchClass]) -> Vec<String> {
+        classes.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn key_signatures() {
+        let e_major = Key::major(spelled("E")).unwrap();
+        assert_eq!(e_major.signature().sharps(), 4);
+        assert_eq!(
+            names(&e_major.signature().accidentals()),
+            ["F#", "C#", "G#", "D#"]
+        );
+
+        let c_minor = Key::minor(spelled("C")).unwrap();
+        assert_eq!(c_minor.signature().flats(), 3);
+        assert_eq!(
+            names(&c_minor.signature().accidentals()),
+            ["Bb", "Eb", "Ab"]
+        );
+
+        let d_dorian = Key::new(spelled("D"), Mode::Dorian).unwrap();
+        assert_eq!(d_dorian.signature().fifths(), 0);
+
+        let g_sharp_major = Key::major(spelled("G#")).unwrap();
+        assert_eq!(
+            names(&g_sharp_major.spelled_degrees()),
+            ["G#", "A#", "B#", "C#", "D#", "E#", "F##"]
+        );
+
+        assert_eq!(
+            Key::major(spelled("B##")),
+            Err(NewKeyError::SignatureTooRemote)
+        );
+    }
+
+    #[test]
+    fn enharmonic_keys_are_spelled_differently() {
+        let f_sharp = Key::major(spelled("F#")).unwrap();
+        let g_flat = Key::major(spelled("Gb")).unwrap();
+        assert_eq!(f_sharp.scale(), g_flat.scale());
+        assert_eq!(
+            names(&f_sharp.spelled_degrees()),
+            ["F#", "G#", "A#", "B", "C#", "D#", "E#"]
+        );
+        assert_eq!(
+            names(&g_flat.spelled_degrees()),
+            ["Gb", "Ab", "Bb", "Cb", "Db", "Eb", "F"]
+        );
+    }
+
+    #[test]
+    fn related_keys() {
+        let c_major = Key::major(spelled("C")).unwrap();
+        assert_eq!(
+            c_major.relative_major_or_minor(),
+            Key::minor(spelled("A")).unwrap()
+        );
+        assert_eq!(
+            Key::minor(spelled("A")).unwrap().relative_major_or_minor(),
+            c_major
+        );
+        assert_eq!(
+            c_major.parallel(Mode::Aeolian).unwrap().signature().flats(),
+            3
+        );
+        assert_eq!(
+            c_major.dominant_key().unwrap(),
+            Key::major(spelled("G")).unwrap()
+        );
+        assert_eq!(
+            c_major.subdominant_key().unwrap(),
+            Key::major(spelled("F")).unwrap()
+        );
+        assert_eq!(
+            c_major.relative(Mode::Phrygian),
+            Key::new(spelled("E"), Mode::Phrygian).unwrap()
+        );
+    }
+
+    #[test]
+    fn spelling_in_context() {
+        let c_major = Key::major(spelled("C")).unwrap();
+        assert_eq!(c_major.spell(NotePitchClass::As).to_string(), "Bb");
+        assert_eq!(c_major.spell(NotePitchClass::Fs).to_string(), "F#");
+        assert_eq!(c_major.spell(NotePitchClass::Ds).to_string(), "Eb");
+
+        let a_minor = Key::minor(spelled("A")).unwrap();
+        assert_eq!(a_minor.spell(NotePitchClass::Gs).to_string(), "G#");
+        assert_eq!(a_minor.spell(NotePitchClass::As).to_string(), "Bb");
+
+        let d_flat_major = Key::major(spelled("Db")).unwrap();
+        assert_eq!(d_flat_major.spell(NotePitchClass::B).to_string(), "Cb");
+        assert!(d_flat_major.contains(NotePitchClass::F));
+        assert!(!d_flat_major.contains(NotePitchClass::E));
+    }
+
+    #[test]
+    fn diatonic_chords() {
+        let c_major = Key::major(spelled("C")).unwrap();
+        let triads = c_major.diatonic_triads();
+        assert_eq!(triads.len(), 7);
+        assert_eq!(triads[4].root(), NotePitchClass::G);
+        assert_eq!(
+            triads[4].chord_class().note_pitch_classes(),
+            &HashSet::from([NotePitchClass::G, NotePitchClass::B, NotePitchClass::D])
+        );
+
+        let sevenths = c_major.diatonic_seventh_chords();
+        assert_eq!(
+            sevenths[6].chord_class().note_pitch_classes(),
+            &HashSet::from([
+                NotePitchClass::B,
+                NotePitchClass::D,
+                NotePitchClass::F,
+                NotePitchClass::A
+            ])
+        );
+    }
+}
@@ -34,20 +34,59 @@
 //! This library provides an optional `serde` feature; when enabled, you can serialize and deserialize all
 //! of the data structures in this crate.
 
+/// Beaming the notes of a bar by its beat groups.
+pub mod beaming;
+
 /// Composition objects.
 pub mod composition;
 
+/// Dynamics, hairpins and their MIDI velocities.
+pub mod dynamics;
+
+/// Swing and groove templates applied when rendering timelines.
+pub mod groove;
+
 /// Harmony objects; contains constructs for chords.
 pub mod harmony;
 
 /// Intervals between pitches.
 pub mod interval;
 
+/// Keys and key signatures.
+pub mod key;
+
+/// Bars, beats and positions within them.
+pub mod measure;
+
+/// Standard MIDI File import and export.
+pub mod midi;
+
 /// Objects for notes which are expressions of pitch in a composition.
 pub mod note;
 
 /// Pitch, tuning, and labeled pitches.
 pub mod pitch;
 
+/// Quantizing performed timings to score durations.
+pub mod quantize;
+
 /// Tempo, metre, and compound rhythms.
 pub mod rhythm;
+
+/// Roman numeral analysis of chords in a key.
+pub mod roman_numeral;
+
+/// Scala scale and keyboard mapping files.
+pub mod scala;
+
+/// Scales and modes.
+pub mod scale;
+
+/// Tempo maps with changes, ramps and fermatas.
+pub mod tempo;
+
+/// Voice leading between chords and its classical errors.
+pub mod voice_leading;
+
+/// Four-part voicing of chord progressions.
+pub mod voicing;
@@ -0,0 +1,438 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    composition::Timeline,
+    note,
+    rhythm::{self, Duration},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An exact place in a piece: a bar, a beat within it, and how far past that beat, with bars and
+/// beats counted from zero.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    bar: u32,
+    beat: u32,
+    remainder: Duration,
+}
+
+impl Position {
+    #[must_use]
+    pub fn new(bar: u32, beat: u32, remainder: Duration) -> Self {
+        Self {
+            bar,
+            beat,
+            remainder,
+        }
+    }
+
+    #[must_use]
+    pub fn bar(&self) -> u32 {
+        self.bar
+    }
+
+    #[must_use]
+    pub fn beat(&self) -> u32 {
+        self.beat
+    }
+
+    #[must_use]
+    pub fn remainder(&self) -> Duration {
+        self.remainder
+    }
+}
+
+/// Shows the position as musicians count it, from one: `3:2+1/8` is an eighth past the second
+/// beat of the third bar.
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.bar + 1, self.beat + 1)?;
+        if !self.remainder.is_zero() {
+            write!(f, "+{}", self.remainder)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BarMapError {
+    EmptyBar,
+    ZeroBeat,
+    /// A time signature can only change at the start of a bar.
+    NotOnBarline,
+}
+
+impl Display for BarMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::EmptyBar => "a time signature must give bars a length",
+            Self::ZeroBeat => "a beat must have a length",
+            Self::NotOnBarline => "time signatures can only change at a barline",
+        })
+    }
+}
+
+impl Error for BarMapError {}
+
+/// A stretch of bars sharing a time signature and beat.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Section {
+    bar: u32,
+    start: Duration,
+    bar_length: Duration,
+    beat: Duration,
+    time_signature: rhythm::TimeSignature,
+}
+
+/// The bars of a piece, allowing the time signature and beat to change at any barline.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BarMap {
+    /// Sorted by bar, starting with bar zero.
+    sections: Vec<Section>,
+}
+
+impl BarMap {
+    /// # Errors
+    ///
+    /// Returns a [`BarMapError`] if the time signature or the beat has no length.
+    pub fn new(time_signature: rhythm::TimeSignature, beat: Duration) -> Result<Self, BarMapError> {
+        Ok(Self {
+            sections: vec![Self::section(0, Duration::zero(), time_signature, beat)?],
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Returns a [`BarMapError`] if the metre's time signature or beat has no length.
+    pub fn from_metre(metre: &rhythm::Metre) -> Result<Self, BarMapError> {
+        Self::new(
+            metre.time_signature().clone(),
+            *metre.beat().beat_assignment().beat_duration(),
+        )
+    }
+
+    fn section(
+        bar: u32,
+        start: Duration,
+        time_signature: rhythm::TimeSignature,
+        beat: Duration,
+    ) -> Result<Section, BarMapError> {
+        let bar_length = time_signature.bar_duration();
+        if bar_length.is_zero() {
+            return Err(BarMapError::EmptyBar);
+        }
+        if beat.is_zero() {
+            return Err(BarMapError::ZeroBeat);
+        }
+        Ok(Section {
+            bar,
+            start,
+            bar_length,
+            beat,
+            time_signature,
+        })
+    }
+
+    /// Changes the time signature and beat from `bar` onwards, until the next change after it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BarMapError`] if the time signature or the beat has no length.
+    pub fn change_at_bar(
+        &mut self,
+        bar: u32,
+        time_signature: rhythm::TimeSignature,
+        beat: Duration,
+    ) -> Result<(), BarMapError> {
+        let section = Self::section(bar, Duration::zero(), time_signature, beat)?;
+        match self
+            .sections
+            .binary_search_by_key(&bar, |section| section.bar)
+        {
+            Ok(index) => self.sections[index] = section,
+            Err(index) => self.sections.insert(index, section),
+        }
+        // later sections start at different offsets once the bars before them change length
+        for index in 1..self.sections.len() {
+            let previous = &self.sections[index - 1];
+            let start =
+                previous.start + previous.bar_length * (self.sections[index].bar - previous.bar);
+            self.sections[index].start = start;
+        }
+        Ok(())
+    }
+
+    /// Changes the time signature and beat from the barline at `offset` onwards, as time
+    /// signature changes are given in MIDI files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BarMapError::NotOnBarline`] if no bar starts at `offset`, or another
+    /// [`BarMapError`] if the time signature or the beat has no length.
+    pub fn change_at_offset(
+        &mut self,
+        offset: Duration,
+        time_signature: rhythm::TimeSignature,
+        beat: Duration,
+    ) -> Result<(), BarMapError> {
+        let position = self.position(offset);
+        if position.beat != 0 || !position.remainder.is_zero() {
+            return Err(BarMapError::NotOnBarline);
+        }
+        self.change_at_bar(position.bar, time_signature, beat)
+    }
+
+    fn section_for_bar(&self, bar: u32) -> &Section {
+        let index = self.sections.partition_point(|section| section.bar <= bar);
+        &self.sections[index - 1]
+    }
+
+    fn section_for_offset(&self, offset: Duration) -> &Section {
+        let index = self
+            .sections
+            .partition_point(|section| section.start <= offset);
+        &self.sections[index - 1]
+    }
+
+    #[must_use]
+    pub fn time_signature(&self, bar: u32) -> &rhythm::TimeSignature {
+        &self.section_for_bar(bar).time_signature
+    }
+
+    #[must_use]
+    pub fn beat(&self, bar: u32) -> Duration {
+        self.section_for_bar(bar).beat
+    }
+
+    #[must_use]
+    pub fn bar_length(&self, bar: u32) -> Duration {
+        self.section_for_bar(bar).bar_length
+    }
+
+    /// The offset at which `bar` starts.
+    #[must_use]
+    pub fn bar_start(&self, bar: u32) -> Duration {
+        let section = self.section_for_bar(bar);
+        section.start + section.bar_length * (bar - section.bar)
+    }
+
+    /// The number of beats that start in `bar`; the last may be cut short by the barline.
+    #[must_use]
+    pub fn beats_in_bar(&self, bar: u32) -> u32 {
+        let section = self.section_for_bar(bar);
+        let beats = section.bar_length / section.beat;
+        beats.floor() + u32::from(beats.denominator() != 1)
+    }
+
+    /// The bar, beat and remainder at `offset`.
+    #[must_use]
+    pub fn position(&self, offset: Duration) -> Position {
+        let section = self.section_for_offset(offset);
+        let bars = ((offset - section.start) / section.bar_length).floor();
+        let bar = section.bar + bars;
+        let within_bar = offset - section.start - section.bar_length * bars;
+        let beat = (within_bar / section.beat).floor();
+        Position {
+            bar,
+            beat,
+            remainder: within_bar - section.beat * beat,
+        }
+    }
+
+    /// The offset of `position`, or `None` if its beat and remainder reach past the end of its
+    /// bar.
+    #[must_use]
+    pub fn offset(&self, position: &Position) -> Option<Duration> {
+        let section = self.section_for_bar(position.bar);
+        let within_bar = section.beat * position.beat + position.remainder;
+        (within_bar < section.bar_length).then(|| self.bar_start(position.bar) + within_bar)
+    }
+
+    /// Splits `duration` starting at `offset` into the parts that fall in each bar, in order.
+    #[must_use]
+    pub fn split_at_barlines(&self, offset: Duration, duration: Duration) -> Vec<Duration> {
+        let end = offset + duration;
+        let mut parts = Vec::new();
+        let mut start = offset;
+        while start < end {
+            let next_bar = self.bar_start(self.position(start).bar + 1);
+            let part_end = next_bar.min(end);
+            parts.push(part_end - start);
+            start = part_end;
+        }
+        parts
+    }
+
+    /// Splits a note starting at `offset` into tied notes, one per bar it sounds in, keeping its
+    /// markings.
+    #[must_use]
+    pub fn split_note(&self, offset: Duration, note: &note::Note) -> note::TiedNote {
+        let mut durations = self.split_at_barlines(offset, *note.duration());
+        if durations.is_empty() {
+            durations.push(Duration::zero());
+        }
+        note::TiedNote::from_note(note, durations)
+            .unwrap_or_else(|_| unreachable!("there is always at least one duration"))
+    }
+
+    /// Rewrites a timeline of notes so that notes crossing barlines become tied notes.
+    #[must_use]
+    pub fn tie_across_barlines(&self, timeline: &Timeline<note::Note>) -> Timeline<note::Event> {
+        timeline
+            .iter()
+            .map(|(offset, note)| {
+                let tied = self.split_note(*offset, note);
+                let event = if tied.durations().len() == 1 {
+                    note::Event::Note(note.clone())
+                } else {
+                    note::Event::TiedNote(tied)
+                };
+                (*offset, event)
+            })
+            .collect()
+    }
+
+    /// Iterates bar by bar over `timeline` up to its last event, giving each bar's number and the
+    /// events that start in it, with offsets relative to the start of the bar.
+    pub fn bars<'a, V: Clone>(
+        &'a self,
+        timeline: &'a Timeline<V>,
+    ) -> impl Iterator<Item = (u32, Timeline<V>)> + 'a {
+        let bar_count = timeline
+            .last_offset()
+            .map_or(0, |last| self.position(last).bar + 1);
+        (0..bar_count).map(|bar| {
+            (
+                bar,
+                timeline.slice(self.bar_start(bar), self.bar_start(bar + 1)),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dynamics::Dynamic;
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::TimeSignature;
+
+    use super::*;
+
+    /// Two bars of 3/4, then 6/8 beating in dotted quarters.
+    fn changing_metre() -> BarMap {
+        let mut bars = BarMap::new(
+            TimeSignature::new(3, 4).unwrap(),
+            Duration::new(1, 4).unwrap(),
+        )
+        .unwrap();
+        bars.change_at_offset(
+            Duration::new(6, 4).unwrap(),
+            TimeSignature::new(6, 8).unwrap(),
+            Duration::new(3, 8).unwrap(),
+        )
+        .unwrap();
+        bars
+    }
+
+    #[test]
+    fn positions_and_offsets() {
+        let mut bars = changing_metre();
+        assert_eq!(
+            bars.position(Duration::zero()),
+            Position::new(0, 0, Duration::zero())
+        );
+        assert_eq!(
+            bars.position(Duration::new(5, 8).unwrap()),
+            Position::new(0, 2, Duration::new(1, 8).unwrap())
+        );
+        assert_eq!(
+            bars.position(Duration::new(3, 4).unwrap()).to_string(),
+            "2:1"
+        );
+        assert_eq!(
+            bars.position(Duration::new(2, 1).unwrap()),
+            Position::new(2, 1, Duration::new(1, 8).unwrap())
+        );
+        assert_eq!(bars.beats_in_bar(1), 3);
+        assert_eq!(bars.beats_in_bar(2), 2);
+
+        for numerator in 0..40 {
+            let offset = Duration::new(1, 16).unwrap() * numerator;
+            assert_eq!(bars.offset(&bars.position(offset)), Some(offset));
+        }
+        assert_eq!(bars.offset(&Position::new(0, 3, Duration::zero())), None);
+
+        assert_eq!(
+            bars.change_at_offset(
+                Duration::new(1, 4).unwrap(),
+                TimeSignature::new(2, 4).unwrap(),
+                Duration::new(1, 4).unwrap()
+            ),
+            Err(BarMapError::NotOnBarline)
+        );
+        assert_eq!(
+            BarMap::new(TimeSignature::default(), Duration::zero()),
+            Err(BarMapError::ZeroBeat)
+        );
+    }
+
+    #[test]
+    fn changing_an_earlier_bar_moves_later_sections() {
+        let mut bars = changing_metre();
+        bars.change_at_bar(
+            1,
+            TimeSignature::new(2, 4).unwrap(),
+            Duration::new(1, 4).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bars.bar_start(2), Duration::new(5, 4).unwrap());
+        assert_eq!(bars.bar_length(2), Duration::new(3, 4).unwrap());
+        assert_eq!(bars.bar_start(3), Duration::new(2, 1).unwrap());
+    }
+
+    #[test]
+    fn notes_are_tied_across_barlines() {
+        let bars = changing_metre();
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        let long = note::Note::new(c4, Duration::new(1, 1).unwrap()).with_dynamic(Dynamic::P);
+        let tied = bars.split_note(Duration::new(1, 2).unwrap(), &long);
+        assert_eq!(
+            tied.durations(),
+            &[Duration::new(1, 4).unwrap(), Duration::new(3, 4).unwrap()]
+        );
+
+        let mut melody = Timeline::new();
+        melody.insert(
+            Duration::zero(),
+            note::Note::new(c4, Duration::new(1, 4).unwrap()),
+        );
+        melody.insert(Duration::new(1, 2).unwrap(), long);
+        melody.insert(
+            Duration::new(3, 2).unwrap(),
+            note::Note::new(c4, Duration::new(3, 8).unwrap()),
+        );
+        let events = bars.tie_across_barlines(&melody);
+        let note::Event::TiedNote(tied) = &events.events_at(&Duration::new(1, 2).unwrap())[0]
+        else {
+            panic!("the long note crosses a barline");
+        };
+        assert_eq!(tied.dynamic(), Some(Dynamic::P));
+        assert!(matches!(
+            events.events_at(&Duration::zero())[0],
+            note::Event::Note(_)
+        ));
+        assert_eq!(events.end(), melody.end());
+
+        let by_bar: Vec<_> = bars
+            .bars(&melody)
+            .map(|(bar, events)| (bar, events.len()))
+            .collect();
+        assert_eq!(by_bar, vec![(0, 2), (1, 0), (2, 1)]);
+    }
+}
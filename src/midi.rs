@@ -0,0 +1,1256 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::Display,
+    io,
+};
+
+use crate::{composition, dynamics, note, pitch, rhythm};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub type Ticks = u32;
+
+/// The layout of the tracks in a Standard MIDI File.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Format {
+    /// A single track holding every event.
+    SingleTrack = 0,
+    /// Several tracks played at the same time, the first of which conventionally holds the tempo
+    /// and time signature events.
+    Simultaneous = 1,
+    /// Several independent single-track sequences.
+    Sequential = 2,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteOptions {
+    format: Format,
+    ppq: u16,
+    channel: u8,
+    velocity: u8,
+    velocity_map: dynamics::VelocityMap,
+    track_dynamics: Vec<TrackDynamics>,
+}
+
+impl WriteOptions {
+    #[must_use]
+    pub fn new(format: Format, ppq: u16) -> Self {
+        Self {
+            format,
+            ppq,
+            ..Self::default()
+        }
+    }
+
+    /// The channel, in `0..16`, that note events are written to.
+    #[must_use]
+    pub fn with_channel(self, channel: u8) -> Self {
+        Self { channel, ..self }
+    }
+
+    /// The note-on velocity, in `1..128`, used for notes before the first dynamic level.
+    #[must_use]
+    pub fn with_velocity(self, velocity: u8) -> Self {
+        Self { velocity, ..self }
+    }
+
+    /// The velocities of the dynamics and accents marked on notes of timelines without their
+    /// own [`TrackDynamics`].
+    #[must_use]
+    pub fn with_velocity_map(self, velocity_map: dynamics::VelocityMap) -> Self {
+        Self {
+            velocity_map,
+            ..self
+        }
+    }
+
+    /// The velocity map and hairpins of each timeline, in order. Timelines past the end use
+    /// [`WriteOptions::velocity_map`] and no hairpins.
+    #[must_use]
+    pub fn with_track_dynamics(self, track_dynamics: Vec<TrackDynamics>) -> Self {
+        Self {
+            track_dynamics,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Pulses (ticks) per quarter note.
+    #[must_use]
+    pub fn ppq(&self) -> u16 {
+        self.ppq
+    }
+
+    #[must_use]
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    #[must_use]
+    pub fn velocity(&self) -> u8 {
+        self.velocity
+    }
+
+    #[must_use]
+    pub fn velocity_map(&self) -> &dynamics::VelocityMap {
+        &self.velocity_map
+    }
+
+    #[must_use]
+    pub fn track_dynamics(&self) -> &[TrackDynamics] {
+        &self.track_dynamics
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            format: Format::Simultaneous,
+            ppq: 480,
+            channel: 0,
+            velocity: 64,
+            velocity_map: dynamics::VelocityMap::default(),
+            track_dynamics: Vec::new(),
+        }
+    }
+}
+
+/// How the dynamics of one timeline are played when it is written.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrackDynamics {
+    velocity_map: dynamics::VelocityMap,
+    hairpins: composition::Timeline<dynamics::Hairpin>,
+}
+
+impl TrackDynamics {
+    #[must_use]
+    pub fn new(velocity_map: dynamics::VelocityMap) -> Self {
+        Self {
+            velocity_map,
+            hairpins: composition::Timeline::new(),
+        }
+    }
+
+    /// The crescendos and diminuendos over the timeline, at the same offsets as its notes.
+    #[must_use]
+    pub fn with_hairpins(self, hairpins: composition::Timeline<dynamics::Hairpin>) -> Self {
+        Self { hairpins, ..self }
+    }
+
+    #[must_use]
+    pub fn velocity_map(&self) -> &dynamics::VelocityMap {
+        &self.velocity_map
+    }
+
+    #[must_use]
+    pub fn hairpins(&self) -> &composition::Timeline<dynamics::Hairpin> {
+        &self.hairpins
+    }
+}
+
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    UnsupportedFormat(Format),
+    /// A single-track file was requested for a number of timelines other than one, or there are
+    /// more timelines than a MIDI file can hold.
+    WrongTrackCount(usize),
+    InvalidPpq(u16),
+    InvalidChannel(u8),
+    InvalidVelocity(u8),
+    /// The duration does not fall on a whole tick at the requested resolution.
+    InexactTicks(rhythm::Duration),
+    /// The duration is too long to be expressed in ticks.
+    TooManyTicks(rhythm::Duration),
+    PitchOutOfRange(pitch::NotePitch),
+    /// The note at this offset has no length, so its note-off could not follow its note-on.
+    ZeroLengthNote(rhythm::Duration),
+    /// The time signature's denominator is not a power of two, or its beat is not a whole number
+    /// of MIDI clocks.
+    UnrepresentableTimeSignature(rhythm::TimeSignature),
+    UnrepresentableTempo(rhythm::Tempo),
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "i/o error: {error}"),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "writing format {} is not supported", *format as u8)
+            }
+            Self::WrongTrackCount(count) => {
+                write!(f, "cannot write {count} tracks in the requested format")
+            }
+            Self::InvalidPpq(ppq) => write!(f, "invalid pulses per quarter note {ppq}"),
+            Self::InvalidChannel(channel) => write!(f, "invalid channel {channel}"),
+            Self::InvalidVelocity(velocity) => write!(f, "invalid velocity {velocity}"),
+            Self::InexactTicks(duration) => {
+                write!(f, "duration {duration} is not a whole number of ticks")
+            }
+            Self::TooManyTicks(duration) => write!(f, "duration {duration} is too long"),
+            Self::PitchOutOfRange(note_pitch) => {
+                write!(f, "pitch {note_pitch:?} is outside of the MIDI range")
+            }
+            Self::ZeroLengthNote(offset) => write!(f, "note at {offset} has no length"),
+            Self::UnrepresentableTimeSignature(time_signature) => {
+                write!(f, "time signature {time_signature:?} cannot be written")
+            }
+            Self::UnrepresentableTempo(tempo) => {
+                write!(f, "tempo of {} bpm cannot be written", tempo.bpm())
+            }
+        }
+    }
+}
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Writes `tracks` as a Standard MIDI File, taking the tempo and time signature from `metre`.
+///
+/// A [`Format::SingleTrack`] file puts the tempo, time signature and notes of the only timeline
+/// in one track. A [`Format::Simultaneous`] file starts with a track holding the tempo and time
+/// signature, followed by one track per timeline.
+///
+/// # Errors
+///
+/// Returns a [`WriteError`] if the options are out of range, if something in the input cannot be
+/// represented in a MIDI file, or if writing to `writer` fails. Nothing is written unless the
+/// whole file can be encoded.
+pub fn write_smf<W: io::Write>(
+    writer: &mut W,
+    tracks: &[composition::Timeline<note::Note>],
+    metre: &rhythm::Metre,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    if options.ppq() == 0 || options.ppq() > 0x7FFF {
+        return Err(WriteError::InvalidPpq(options.ppq()));
+    }
+    if options.channel() > 15 {
+        return Err(WriteError::InvalidChannel(options.channel()));
+    }
+    if options.velocity() == 0 || options.velocity() > 127 {
+        return Err(WriteError::InvalidVelocity(options.velocity()));
+    }
+
+    let conductor = conductor_events(metre)?;
+    let track_chunks = match options.format() {
+        Format::SingleTrack => {
+            let [track] = tracks else {
+                return Err(WriteError::WrongTrackCount(tracks.len()));
+            };
+            let mut events = conductor;
+            events.extend(note_events(
+                track,
+                options.track_dynamics().first(),
+                options,
+            )?);
+            vec![encode_track(events)]
+        }
+        Format::Simultaneous => {
+            let mut chunks = vec![encode_track(conductor)];
+            for (index, track) in tracks.iter().enumerate() {
+                let dynamics = options.track_dynamics().get(index);
+                chunks.push(encode_track(note_events(track, dynamics, options)?));
+            }
+            chunks
+        }
+        Format::Sequential => return Err(WriteError::UnsupportedFormat(options.format())),
+    };
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"MThd");
+    bytes.extend_from_slice(&6u32.to_be_bytes());
+    bytes.extend_from_slice(&(options.format() as u16).to_be_bytes());
+    let track_count =
+        u16::try_from(track_chunks.len()).map_err(|_| WriteError::WrongTrackCount(tracks.len()))?;
+    bytes.extend_from_slice(&track_count.to_be_bytes());
+    bytes.extend_from_slice(&options.ppq().to_be_bytes());
+    for chunk in track_chunks {
+        bytes.extend_from_slice(&chunk);
+    }
+
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Converts a score duration into ticks at `ppq` pulses per quarter note.
+///
+/// # Errors
+///
+/// Returns [`WriteError::InexactTicks`] if the duration does not land on a whole tick and
+/// [`WriteError::TooManyTicks`] if it does not fit in a [`Ticks`].
+pub fn duration_to_ticks(duration: rhythm::Duration, ppq: u16) -> Result<Ticks, WriteError> {
+    let ticks = duration
+        .ratio()
+        .checked_mul(rhythm::Ratio::from(4 * u32::from(ppq)))
+        .ok_or(WriteError::TooManyTicks(duration))?;
+    if ticks.denominator() != 1 {
+        return Err(WriteError::InexactTicks(duration));
+    }
+    Ok(ticks.numerator())
+}
+
+/// A channel or meta event waiting to be sorted into a track.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct TrackEvent {
+    tick: Ticks,
+    // at equal ticks: meta events first, then note-offs, then note-ons
+    priority: u8,
+    bytes: Vec<u8>,
+}
+
+fn conductor_events(metre: &rhythm::Metre) -> Result<Vec<TrackEvent>, WriteError> {
+    let rhythm = metre.beat();
+    let beat_ratio = *rhythm.beat_assignment().beat_duration().ratio();
+    let quarter = rhythm::Ratio::new(1, 4).unwrap();
+
+    let tempo = rhythm.tempo();
+    let quarter_seconds = tempo.one_beat_seconds() * (quarter / beat_ratio).to_f64();
+    let microseconds = (quarter_seconds * 1_000_000.0).round();
+    if !microseconds.is_finite() || microseconds < 1.0 || microseconds > f64::from(0x00FF_FFFF) {
+        return Err(WriteError::UnrepresentableTempo(*tempo));
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let microseconds = microseconds as u32;
+
+    let time_signature = metre.time_signature();
+    let unrepresentable = || WriteError::UnrepresentableTimeSignature(time_signature.clone());
+    let numerator = u8::try_from(time_signature.numerator()).map_err(|_| unrepresentable())?;
+    let denominator = time_signature.denominator();
+    if !denominator.is_power_of_two() {
+        return Err(unrepresentable());
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let denominator_power = denominator.trailing_zeros() as u8;
+    // MIDI clocks tick 24 times per quarter note
+    let clocks = beat_ratio
+        .checked_mul(rhythm::Ratio::from(96))
+        .filter(|clocks| clocks.denominator() == 1)
+        .and_then(|clocks| u8::try_from(clocks.numerator()).ok())
+        .ok_or_else(unrepresentable)?;
+
+    let mut tempo_event = vec![0xFF, 0x51, 0x03];
+    tempo_event.extend_from_slice(&microseconds.to_be_bytes()[1..]);
+    Ok(vec![
+        TrackEvent {
+            tick: 0,
+            priority: 0,
+            bytes: tempo_event,
+        },
+        TrackEvent {
+            tick: 0,
+            priority: 0,
+            bytes: vec![0xFF, 0x58, 0x04, numerator, denominator_power, clocks, 8],
+        },
+    ])
+}
+
+fn note_events(
+    track: &composition::Timeline<note::Note>,
+    dynamics: Option<&TrackDynamics>,
+    options: &WriteOptions,
+) -> Result<Vec<TrackEvent>, WriteError> {
+    let mut events = Vec::with_capacity(track.len() * 2);
+    let velocities = match dynamics {
+        Some(dynamics) => {
+            dynamics
+                .velocity_map()
+                .velocities(track, dynamics.hairpins(), options.velocity())
+        }
+        None => options.velocity_map().velocities(
+            track,
+            &composition::Timeline::new(),
+            options.velocity(),
+        ),
+    };
+    for ((offset, note), velocity) in track.iter().zip(velocities) {
+        let key = note
+            .note_pitch()
+            .midi_number()
+            .ok_or(WriteError::PitchOutOfRange(*note.note_pitch()))?;
+        if note.duration().is_zero() {
+            return Err(WriteError::ZeroLengthNote(*offset));
+        }
+        let end = offset
+            .checked_add(*note.duration())
+            .ok_or(WriteError::TooManyTicks(*offset))?;
+        events.push(TrackEvent {
+            tick: duration_to_ticks(*offset, options.ppq())?,
+            priority: 2,
+            bytes: vec![0x90 | options.channel(), key, velocity],
+        });
+        events.push(TrackEvent {
+            tick: duration_to_ticks(end, options.ppq())?,
+            priority: 1,
+            bytes: vec![0x80 | options.channel(), key, 0],
+        });
+    }
+    Ok(events)
+}
+
+fn encode_track(mut events: Vec<TrackEvent>) -> Vec<u8> {
+    events.sort();
+
+    let mut data = Vec::new();
+    let mut previous_tick = 0;
+    for event in events {
+        write_variable_length(&mut data, event.tick - previous_tick);
+        data.extend_from_slice(&event.bytes);
+        previous_tick = event.tick;
+    }
+    data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut chunk = Vec::with_capacity(data.len() + 8);
+    chunk.extend_from_slice(b"MTrk");
+    // a track holds at most two events per note, far below u32::MAX bytes in practice
+    #[allow(clippy::cast_possible_truncation)]
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(&data);
+    chunk
+}
+
+fn write_variable_length(bytes: &mut Vec<u8>, mut value: u32) {
+    let mut buffer = [0u8; 5];
+    let mut index = buffer.len() - 1;
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        buffer[index] = (value & 0x7F) as u8;
+        value >>= 7;
+        while value > 0 {
+            index -= 1;
+            buffer[index] = (value & 0x7F) as u8 | 0x80;
+            value >>= 7;
+        }
+    }
+    bytes.extend_from_slice(&buffer[index..]);
+}
+
+impl TryFrom<u16> for Format {
+    type Error = ReadError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::SingleTrack),
+            1 => Ok(Self::Simultaneous),
+            2 => Ok(Self::Sequential),
+            _ => Err(ReadError::UnsupportedFormat(value)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReadOptions {
+    grid: Option<rhythm::Duration>,
+}
+
+impl ReadOptions {
+    /// Options that convert ticks into durations exactly.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rounds every note start and end to the nearest multiple of `grid`. A zero grid is ignored.
+    #[must_use]
+    pub fn with_grid(self, grid: rhythm::Duration) -> Self {
+        Self {
+            grid: (!grid.is_zero()).then_some(grid),
+        }
+    }
+
+    #[must_use]
+    pub fn grid(&self) -> Option<rhythm::Duration> {
+        self.grid
+    }
+}
+
+/// The contents of a Standard MIDI File.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Smf {
+    format: Format,
+    ppq: u16,
+    tracks: Vec<composition::Timeline<note::Note>>,
+    tempo_changes: composition::Timeline<rhythm::Tempo>,
+    time_signature_changes: composition::Timeline<rhythm::TimeSignature>,
+}
+
+impl Smf {
+    #[must_use]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    #[must_use]
+    pub fn ppq(&self) -> u16 {
+        self.ppq
+    }
+
+    /// The notes of every track, on all channels, in the order the tracks appear in the file.
+    #[must_use]
+    pub fn tracks(&self) -> &[composition::Timeline<note::Note>] {
+        &self.tracks
+    }
+
+    /// Tempo changes from every track. Tempos count quarter notes per minute.
+    #[must_use]
+    pub fn tempo_changes(&self) -> &composition::Timeline<rhythm::Tempo> {
+        &self.tempo_changes
+    }
+
+    /// Time signature changes from every track.
+    #[must_use]
+    pub fn time_signature_changes(&self) -> &composition::Timeline<rhythm::TimeSignature> {
+        &self.time_signature_changes
+    }
+}
+
+/// Why a Standard MIDI File could not be read. Offsets are byte positions in the input.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ReadError {
+    UnexpectedEof {
+        offset: usize,
+    },
+    MissingHeader,
+    InvalidHeaderLength(u32),
+    UnsupportedFormat(u16),
+    /// SMPTE time divisions and a zero ticks-per-quarter division are not supported.
+    UnsupportedDivision(u16),
+    /// The header announces more tracks than the file contains.
+    MissingTracks {
+        expected: u16,
+        found: u16,
+    },
+    InvalidVariableLength {
+        offset: usize,
+    },
+    /// A data byte appeared before any status byte it could belong to.
+    MissingRunningStatus {
+        offset: usize,
+    },
+    InvalidStatus {
+        offset: usize,
+        status: u8,
+    },
+    InvalidDataByte {
+        offset: usize,
+        byte: u8,
+    },
+    InvalidMetaEvent {
+        offset: usize,
+        kind: u8,
+    },
+    /// A chunk or event extends past the end of its track chunk.
+    TrackOverrun {
+        offset: usize,
+    },
+    /// An event lies too many ticks into its track to be represented as a duration.
+    TickOverflow {
+        tick: u64,
+    },
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof { offset } => write!(f, "unexpected end of file at byte {offset}"),
+            Self::MissingHeader => f.write_str("file does not start with an MThd chunk"),
+            Self::InvalidHeaderLength(length) => write!(f, "invalid header length {length}"),
+            Self::UnsupportedFormat(format) => write!(f, "unsupported format {format}"),
+            Self::UnsupportedDivision(division) => {
+                write!(f, "unsupported time division {division:#06x}")
+            }
+            Self::MissingTracks { expected, found } => {
+                write!(f, "expected {expected} tracks but found {found}")
+            }
+            Self::InvalidVariableLength { offset } => {
+                write!(f, "invalid variable-length quantity at byte {offset}")
+            }
+            Self::MissingRunningStatus { offset } => {
+                write!(f, "data byte without a running status at byte {offset}")
+            }
+            Self::InvalidStatus { offset, status } => {
+                write!(f, "invalid status {status:#04x} at byte {offset}")
+            }
+            Self::InvalidDataByte { offset, byte } => {
+                write!(f, "invalid data byte {byte:#04x} at byte {offset}")
+            }
+            Self::InvalidMetaEvent { offset, kind } => {
+                write!(f, "invalid meta event {kind:#04x} at byte {offset}")
+            }
+            Self::TrackOverrun { offset } => {
+                write!(f, "event at byte {offset} runs past the end of its track")
+            }
+            Self::TickOverflow { tick } => {
+                write!(f, "event at tick {tick} is too far into its track")
+            }
+        }
+    }
+}
+
+impl Error for ReadError {}
+
+/// Parses a Standard MIDI File of any format.
+///
+/// Every track becomes one timeline of notes. Note-on events with a velocity of zero end notes,
+/// overlapping notes of the same key and channel are ended first-in first-out, and notes still
+/// sounding at the end of their track end there. Tempo and time signature changes from all tracks
+/// are gathered together.
+///
+/// # Errors
+///
+/// Returns a [`ReadError`] describing the first problem found in `bytes`.
+pub fn read_smf(bytes: &[u8], options: &ReadOptions) -> Result<Smf, ReadError> {
+    let mut reader = ByteReader { bytes, position: 0 };
+
+    if reader.take(4).map_err(|_| ReadError::MissingHeader)? != b"MThd" {
+        return Err(ReadError::MissingHeader);
+    }
+    let header_length = reader.read_u32()?;
+    if header_length < 6 {
+        return Err(ReadError::InvalidHeaderLength(header_length));
+    }
+    let format = Format::try_from(reader.read_u16()?)?;
+    let expected_tracks = reader.read_u16()?;
+    let division = reader.read_u16()?;
+    if division == 0 || division & 0x8000 != 0 {
+        return Err(ReadError::UnsupportedDivision(division));
+    }
+    reader.take(header_length as usize - 6)?;
+
+    let mut smf = Smf {
+        format,
+        ppq: division,
+        tracks: Vec::new(),
+        tempo_changes: composition::Timeline::new(),
+        time_signature_changes: composition::Timeline::new(),
+    };
+    let converter = TickConverter {
+        ppq: division,
+        grid: options.grid(),
+    };
+
+    while smf.tracks.len() < usize::from(expected_tracks) {
+        if reader.remaining() == 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            return Err(ReadError::MissingTracks {
+                expected: expected_tracks,
+                found: smf.tracks.len() as u16,
+            });
+        }
+        let id = reader.take(4)?;
+        let length = reader.read_u32()? as usize;
+        let start = reader.position;
+        let chunk = reader.take(length)?;
+        // unknown chunk types must be skipped
+        if id == b"MTrk" {
+            let track_reader = ByteReader {
+                bytes: &bytes[..start + chunk.len()],
+                position: start,
+            };
+            // running out of bytes inside a track means its chunk length was wrong, not that the
+            // file is truncated
+            let track =
+                read_track(track_reader, &converter, &mut smf).map_err(|error| match error {
+                    ReadError::UnexpectedEof { offset } => ReadError::TrackOverrun { offset },
+                    other => other,
+                })?;
+            smf.tracks.push(track);
+        }
+    }
+
+    Ok(smf)
+}
+
+/// Reads the rest of a meta event starting at `offset`, returning whether it ended the track.
+fn read_meta_event(
+    reader: &mut ByteReader,
+    offset: usize,
+    tick: u64,
+    converter: &TickConverter,
+    smf: &mut Smf,
+) -> Result<bool, ReadError> {
+    let kind = reader.read_u8()?;
+    let length = reader.read_variable_length()?;
+    let data = reader.take(length as usize)?;
+    let invalid = ReadError::InvalidMetaEvent { offset, kind };
+    match kind {
+        0x2F => return Ok(true),
+        0x51 => {
+            let [a, b, c] = data else { return Err(invalid) };
+            let microseconds = u32::from_be_bytes([0, *a, *b, *c]);
+            if microseconds == 0 {
+                return Err(invalid);
+            }
+            let tempo =
+                rhythm::Tempo::new(60_000_000.0 / f64::from(microseconds)).ok_or(invalid)?;
+            smf.tempo_changes
+                .insert(converter.to_duration(tick)?, tempo);
+        }
+        0x58 => {
+            let [numerator, power, ..] = data else {
+                return Err(invalid);
+            };
+            let time_signature = 1u32
+                .checked_shl(u32::from(*power))
+                .and_then(|denominator| {
+                    rhythm::TimeSignature::new(u32::from(*numerator), denominator).ok()
+                })
+                .ok_or(invalid)?;
+            smf.time_signature_changes
+                .insert(converter.to_duration(tick)?, time_signature);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReadError> {
+        if count > self.remaining() {
+            return Err(ReadError::UnexpectedEof {
+                offset: self.bytes.len(),
+            });
+        }
+        let taken = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(taken)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ReadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ReadError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ReadError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_variable_length(&mut self) -> Result<u32, ReadError> {
+        let offset = self.position;
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.read_u8()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReadError::InvalidVariableLength { offset })
+    }
+
+    fn read_data_byte(&mut self) -> Result<u8, ReadError> {
+        let offset = self.position;
+        let byte = self.read_u8()?;
+        if byte & 0x80 != 0 {
+            return Err(ReadError::InvalidDataByte { offset, byte });
+        }
+        Ok(byte)
+    }
+}
+
+struct TickConverter {
+    ppq: u16,
+    grid: Option<rhythm::Duration>,
+}
+
+impl TickConverter {
+    fn to_duration(&self, tick: u64) -> Result<rhythm::Duration, ReadError> {
+        let overflow = || ReadError::TickOverflow { tick };
+        let ticks_per_whole = 4 * u32::from(self.ppq);
+        let ticks = u32::try_from(tick).map_err(|_| overflow())?;
+        // only zero ticks fail, since the division is never zero
+        let exact = rhythm::Duration::new(ticks, ticks_per_whole).unwrap_or_default();
+        match self.grid {
+            Some(grid) => exact
+                .ratio()
+                .checked_div(*grid.ratio())
+                .and_then(|steps| grid.checked_mul(steps.round().into()))
+                .ok_or_else(overflow),
+            None => Ok(exact),
+        }
+    }
+
+    fn smallest_duration(&self) -> rhythm::Duration {
+        self.grid
+            .unwrap_or_else(|| rhythm::Duration::new(1, 4 * u32::from(self.ppq)).unwrap())
+    }
+}
+
+fn read_track(
+    mut reader: ByteReader,
+    converter: &TickConverter,
+    smf: &mut Smf,
+) -> Result<composition::Timeline<note::Note>, ReadError> {
+    // (channel, key) -> note-ons still sounding as (tick, sequence number), oldest first
+    let mut sounding: HashMap<(u8, u8), VecDeque<(u64, usize)>> = HashMap::new();
+    // (start tick, sequence number, key, end tick), so that notes can be added in note-on order
+    let mut notes = Vec::new();
+    let mut note_ons = 0usize;
+    let mut running_status = None;
+    let mut tick: u64 = 0;
+
+    while reader.remaining() > 0 {
+        tick += u64::from(reader.read_variable_length()?);
+        let offset = reader.position;
+        let first = reader.read_u8()?;
+
+        let status = if first & 0x80 == 0 {
+            // running status: the byte just read is the first data byte
+            reader.position = offset;
+            running_status.ok_or(ReadError::MissingRunningStatus { offset })?
+        } else {
+            first
+        };
+
+        match status {
+            0x80..=0xEF => {
+                running_status = Some(status);
+                let channel = status & 0x0F;
+                let data1 = reader.read_data_byte()?;
+                if matches!(status & 0xF0, 0xC0 | 0xD0) {
+                    continue;
+                }
+                let data2 = reader.read_data_byte()?;
+                match status & 0xF0 {
+                    0x90 if data2 > 0 => {
+                        sounding
+                            .entry((channel, data1))
+                            .or_default()
+                            .push_back((tick, note_ons));
+                        note_ons += 1;
+                    }
+                    0x80 | 0x90 => {
+                        if let Some((start, sequence)) = sounding
+                            .get_mut(&(channel, data1))
+                            .and_then(VecDeque::pop_front)
+                        {
+                            notes.push((start, sequence, data1, tick));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            0xFF => {
+                if read_meta_event(&mut reader, offset, tick, converter, smf)? {
+                    break;
+                }
+            }
+            0xF0 | 0xF7 => {
+                running_status = None;
+                let length = reader.read_variable_length()?;
+                reader.take(length as usize)?;
+            }
+            _ => return Err(ReadError::InvalidStatus { offset, status }),
+        }
+    }
+
+    for ((_, key), starts) in sounding {
+        notes.extend(
+            starts
+                .into_iter()
+                .map(|(start, sequence)| (start, sequence, key, tick)),
+        );
+    }
+    notes.sort_unstable();
+
+    let mut timeline = composition::Timeline::new();
+    for (start, _, key, end) in notes {
+        let start_duration = converter.to_duration(start)?;
+        let duration = converter
+            .to_duration(end)?
+            .checked_sub(start_duration)
+            .filter(|duration| !duration.is_zero())
+            .unwrap_or_else(|| converter.smallest_duration());
+        timeline.insert(
+            start_duration,
+            note::Note::new(
+                pitch::NotePitch::from_midi_number(key)
+                    .unwrap_or_else(|| unreachable!("data bytes are below 128")),
+                duration,
+            ),
+        );
+    }
+    Ok(timeline)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::{BeatAssignment, Duration, Metre, Rhythm, Tempo, TimeSignature};
+
+    use super::*;
+
+    fn three_four() -> Metre {
+        Metre::new(
+            Rhythm::new(
+                Tempo::new(120.0).unwrap(),
+                BeatAssignment::new(Duration::new(1, 4).unwrap()),
+            ),
+            TimeSignature::new(3, 4).unwrap(),
+        )
+    }
+
+    fn middle_c_quarter() -> composition::Timeline<note::Note> {
+        let mut timeline = composition::Timeline::new();
+        timeline.insert(
+            Duration::zero(),
+            note::Note::new(
+                NotePitch::new(NotePitchClass::C, 4),
+                Duration::new(1, 4).unwrap(),
+            ),
+        );
+        timeline
+    }
+
+    #[test]
+    fn variable_length_quantities() {
+        let encode = |value| {
+            let mut bytes = Vec::new();
+            write_variable_length(&mut bytes, value);
+            bytes
+        };
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(0x7F), vec![0x7F]);
+        assert_eq!(encode(0x80), vec![0x81, 0x00]);
+        assert_eq!(encode(0x3FFF), vec![0xFF, 0x7F]);
+        assert_eq!(encode(0x0FFF_FFFF), vec![0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn write_single_track_file() {
+        let mut bytes = Vec::new();
+        write_smf(
+            &mut bytes,
+            &[middle_c_quarter()],
+            &three_four(),
+            &WriteOptions::new(Format::SingleTrack, 96),
+        )
+        .unwrap();
+
+        #[rustfmt::skip]
+        let expected = vec![
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 27,
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+            0x00, 0xFF, 0x58, 0x04, 3, 2, 24, 8,
+            0x00, 0x90, 60, 64,
+            0x60, 0x80, 60, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn write_multi_track_file() {
+        let mut bytes = Vec::new();
+        write_smf(
+            &mut bytes,
+            &[middle_c_quarter(), middle_c_quarter()],
+            &three_four(),
+            &WriteOptions::new(Format::Simultaneous, 96).with_channel(9),
+        )
+        .unwrap();
+
+        assert_eq!(&bytes[8..12], &[0, 1, 0, 3]);
+        #[rustfmt::skip]
+        let note_track = [
+            b'M', b'T', b'r', b'k', 0, 0, 0, 12,
+            0x00, 0x99, 60, 64,
+            0x60, 0x89, 60, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(&bytes[bytes.len() - note_track.len()..], &note_track);
+    }
+
+    #[test]
+    fn write_velocities_from_dynamics() {
+        let mut timeline = composition::Timeline::new();
+        let quarter = Duration::new(1, 4).unwrap();
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        timeline.insert(
+            Duration::zero(),
+            note::Note::new(c4, quarter).with_dynamic(dynamics::Dynamic::F),
+        );
+        timeline.insert(
+            quarter,
+            note::Note::new(c4, quarter).with_articulation(note::Articulation::Accent),
+        );
+        let mut bytes = Vec::new();
+        write_smf(
+            &mut bytes,
+            &[timeline],
+            &three_four(),
+            &WriteOptions::new(Format::SingleTrack, 96),
+        )
+        .unwrap();
+
+        let note_ons: Vec<u8> = bytes
+            .windows(3)
+            .filter(|window| window[0] == 0x90 && window[1] == 60)
+            .map(|window| window[2])
+            .collect();
+        assert_eq!(note_ons, vec![96, 112]);
+
+        // every track has its own velocity map and hairpins
+        let mut swell = composition::Timeline::new();
+        for index in 0..3 {
+            let note = note::Note::new(c4, quarter);
+            let note = if index == 0 {
+                note.with_dynamic(dynamics::Dynamic::P)
+            } else {
+                note
+            };
+            swell.insert(quarter * index, note);
+        }
+        let mut hairpins = composition::Timeline::new();
+        hairpins.insert(
+            Duration::zero(),
+            dynamics::Hairpin::new(dynamics::HairpinKind::Crescendo, quarter * 2)
+                .with_target(dynamics::Dynamic::F),
+        );
+        let quiet = dynamics::VelocityMap::new([10, 20, 30, 40, 50, 60, 70, 80], 90).unwrap();
+        let mut bytes = Vec::new();
+        write_smf(
+            &mut bytes,
+            &[middle_c_quarter(), swell],
+            &three_four(),
+            &WriteOptions::new(Format::Simultaneous, 96).with_track_dynamics(vec![
+                TrackDynamics::new(dynamics::VelocityMap::default()),
+                TrackDynamics::new(quiet).with_hairpins(hairpins),
+            ]),
+        )
+        .unwrap();
+
+        let note_ons: Vec<u8> = bytes
+            .windows(3)
+            .filter(|window| window[0] == 0x90 && window[1] == 60)
+            .map(|window| window[2])
+            .collect();
+        assert_eq!(note_ons, vec![64, 30, 45, 60]);
+    }
+
+    #[test]
+    fn write_rejects_inexact_durations() {
+        let mut timeline = composition::Timeline::new();
+        timeline.insert(
+            Duration::new(1, 7).unwrap(),
+            note::Note::new(
+                NotePitch::new(NotePitchClass::C, 4),
+                Duration::new(1, 4).unwrap(),
+            ),
+        );
+        let result = write_smf(
+            &mut Vec::new(),
+            &[timeline],
+            &three_four(),
+            &WriteOptions::new(Format::SingleTrack, 96),
+        );
+        assert!(matches!(result, Err(WriteError::InexactTicks(_))));
+    }
+
+    #[test]
+    fn write_rejects_zero_length_notes() {
+        let mut timeline = composition::Timeline::new();
+        timeline.insert(
+            Duration::new(1, 4).unwrap(),
+            note::Note::new(NotePitch::new(NotePitchClass::C, 4), Duration::zero()),
+        );
+        let result = write_smf(
+            &mut Vec::new(),
+            &[timeline],
+            &three_four(),
+            &WriteOptions::new(Format::SingleTrack, 96),
+        );
+        assert!(matches!(result, Err(WriteError::ZeroLengthNote(_))));
+    }
+
+    #[test]
+    fn read_round_trips_written_file() {
+        let mut timeline = middle_c_quarter();
+        timeline.insert(
+            Duration::zero(),
+            note::Note::new(
+                NotePitch::new(NotePitchClass::E, 4),
+                Duration::new(1, 12).unwrap(),
+            ),
+        );
+        timeline.insert(
+            Duration::new(3, 8).unwrap(),
+            note::Note::new(
+                NotePitch::new(NotePitchClass::G, 2),
+                Duration::new(3, 16).unwrap(),
+            ),
+        );
+
+        let mut bytes = Vec::new();
+        write_smf(
+            &mut bytes,
+            &[timeline.clone()],
+            &three_four(),
+            &WriteOptions::default(),
+        )
+        .unwrap();
+
+        let smf = read_smf(&bytes, &ReadOptions::new()).unwrap();
+        assert_eq!(smf.format(), Format::Simultaneous);
+        assert_eq!(smf.ppq(), 480);
+        assert_eq!(smf.tracks().len(), 2);
+        assert!(smf.tracks()[0].is_empty());
+        assert_eq!(smf.tracks()[1], timeline);
+
+        let tempos: Vec<_> = smf.tempo_changes().iter().collect();
+        assert_eq!(tempos.len(), 1);
+        assert!((tempos[0].1.bpm() - 120.0).abs() < 1e-9);
+        assert_eq!(
+            smf.time_signature_changes().events_at(&Duration::zero()),
+            &[TimeSignature::new(3, 4).unwrap()]
+        );
+    }
+
+    #[test]
+    fn read_running_status_and_grid() {
+        #[rustfmt::skip]
+        let bytes = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 17,
+            0x00, 0x90, 60, 100,
+            0x05, 64, 100,
+            0x5B, 60, 0,
+            0x02, 64, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+
+        let exact = read_smf(&bytes, &ReadOptions::new()).unwrap();
+        let notes: Vec<_> = exact.tracks()[0].iter().collect();
+        assert_eq!(*notes[0].0, Duration::zero());
+        assert_eq!(*notes[0].1.duration(), Duration::new(1, 4).unwrap());
+        assert_eq!(*notes[1].0, Duration::new(5, 384).unwrap());
+
+        let quantized = read_smf(
+            &bytes,
+            &ReadOptions::new().with_grid(Duration::new(1, 16).unwrap()),
+        )
+        .unwrap();
+        let notes: Vec<_> = quantized.tracks()[0].iter().collect();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(*notes[1].0, Duration::zero());
+        assert_eq!(
+            *notes[1].1,
+            note::Note::new(
+                NotePitch::new(NotePitchClass::E, 4),
+                Duration::new(1, 4).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn read_reports_malformed_files() {
+        assert_eq!(
+            read_smf(b"RIFF", &ReadOptions::new()),
+            Err(ReadError::MissingHeader)
+        );
+
+        #[rustfmt::skip]
+        let smpte = [b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0xE7, 0x28];
+        assert_eq!(
+            read_smf(&smpte, &ReadOptions::new()),
+            Err(ReadError::UnsupportedDivision(0xE728))
+        );
+
+        #[rustfmt::skip]
+        let missing_status = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 3,
+            0x00, 60, 100,
+        ];
+        assert_eq!(
+            read_smf(&missing_status, &ReadOptions::new()),
+            Err(ReadError::MissingRunningStatus { offset: 23 })
+        );
+
+        #[rustfmt::skip]
+        let overrun = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 3,
+            0x00, 0x90, 60, 100,
+        ];
+        assert_eq!(
+            read_smf(&overrun, &ReadOptions::new()),
+            Err(ReadError::TrackOverrun { offset: 25 })
+        );
+
+        #[rustfmt::skip]
+        let truncated = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 4,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(
+            read_smf(&truncated, &ReadOptions::new()),
+            Err(ReadError::MissingTracks {
+                expected: 2,
+                found: 1
+            })
+        );
+
+        #[rustfmt::skip]
+        let far_off = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 1,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 43,
+            0x00, 0x90, 60, 100,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(
+            read_smf(
+                &far_off,
+                &ReadOptions::new().with_grid(Duration::new(1, 16).unwrap())
+            ),
+            Err(ReadError::TickOverflow {
+                tick: 5 * 0x0FFF_FFFF
+            })
+        );
+    }
+}
@@ -1,14 +1,41 @@
+use std::{error::Error, fmt::Display};
+
+use crate::dynamics;
+use crate::harmony;
 use crate::pitch;
 use crate::rhythm;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// How a note is played, apart from its loudness.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Articulation {
+    Staccato,
+    Tenuto,
+    Accent,
+    Marcato,
+}
+
+/// A note's place in a legato slur.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Slur {
+    Start,
+    Middle,
+    End,
+}
+
+#[allow(clippy::struct_field_names)]
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Note {
     note_pitch: pitch::NotePitch,
     duration: rhythm::Duration,
+    dynamic: Option<dynamics::Dynamic>,
+    articulations: Vec<Articulation>,
+    slur: Option<Slur>,
 }
 
 impl Note {
@@ -17,6 +44,35 @@ impl Note {
         Self {
             note_pitch,
             duration,
+            dynamic: None,
+            articulations: Vec::new(),
+            slur: None,
+        }
+    }
+
+    /// Marks the note with `dynamic`.
+    #[must_use]
+    pub fn with_dynamic(self, dynamic: dynamics::Dynamic) -> Self {
+        Self {
+            dynamic: Some(dynamic),
+            ..self
+        }
+    }
+
+    /// Adds `articulation` to the note, unless it already has it.
+    #[must_use]
+    pub fn with_articulation(mut self, articulation: Articulation) -> Self {
+        if !self.articulations.contains(&articulation) {
+            self.articulations.push(articulation);
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn with_slur(self, slur: Slur) -> Self {
+        Self {
+            slur: Some(slur),
+            ..self
         }
     }
 
@@ -29,4 +85,363 @@ impl Note {
     pub fn duration(&self) -> &rhythm::Duration {
         &self.duration
     }
+
+    /// The dynamic marked on the note, if any.
+    #[must_use]
+    pub fn dynamic(&self) -> Option<dynamics::Dynamic> {
+        self.dynamic
+    }
+
+    /// The articulations marked on the note, in the order they were added.
+    #[must_use]
+    pub fn articulations(&self) -> &[Articulation] {
+        &self.articulations
+    }
+
+    #[must_use]
+    pub fn has_articulation(&self, articulation: Articulation) -> bool {
+        self.articulations.contains(&articulation)
+    }
+
+    #[must_use]
+    pub fn slur(&self) -> Option<Slur> {
+        self.slur
+    }
+}
+
+impl rhythm::HasDuration for Note {
+    fn duration(&self) -> rhythm::Duration {
+        self.duration
+    }
+}
+
+/// A silence lasting `duration`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rest {
+    duration: rhythm::Duration,
+}
+
+impl Rest {
+    #[must_use]
+    pub fn new(duration: rhythm::Duration) -> Self {
+        Self { duration }
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> &rhythm::Duration {
+        &self.duration
+    }
+}
+
+impl rhythm::HasDuration for Rest {
+    fn duration(&self) -> rhythm::Duration {
+        self.duration
+    }
+}
+
+/// The pitches of a chord all sounding for the same duration.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordEvent {
+    chord: harmony::Chord,
+    duration: rhythm::Duration,
+}
+
+impl ChordEvent {
+    #[must_use]
+    pub fn new(chord: harmony::Chord, duration: rhythm::Duration) -> Self {
+        Self { chord, duration }
+    }
+
+    #[must_use]
+    pub fn chord(&self) -> &harmony::Chord {
+        &self.chord
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> &rhythm::Duration {
+        &self.duration
+    }
+
+    /// The chord as one note per pitch.
+    #[must_use]
+    pub fn notes(&self) -> Vec<Note> {
+        self.chord
+            .note_pitches()
+            .iter()
+            .map(|note_pitch| Note::new(*note_pitch, self.duration))
+            .collect()
+    }
+}
+
+impl rhythm::HasDuration for ChordEvent {
+    fn duration(&self) -> rhythm::Duration {
+        self.duration
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewTiedNoteError {
+    NoDurations,
+}
+
+impl Display for NewTiedNoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a tied note needs at least one duration")
+    }
+}
+
+impl Error for NewTiedNoteError {}
+
+/// One pitch held through several durations joined by ties, as when a note crosses a barline.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TiedNote {
+    note_pitch: pitch::NotePitch,
+    durations: Vec<rhythm::Duration>,
+    dynamic: Option<dynamics::Dynamic>,
+    articulations: Vec<Articulation>,
+    slur: Option<Slur>,
+}
+
+impl TiedNote {
+    /// # Errors
+    ///
+    /// Returns [`NewTiedNoteError::NoDurations`] if `durations` is empty.
+    pub fn new(
+        note_pitch: pitch::NotePitch,
+        durations: Vec<rhythm::Duration>,
+    ) -> Result<Self, NewTiedNoteError> {
+        if durations.is_empty() {
+            return Err(NewTiedNoteError::NoDurations);
+        }
+        Ok(Self {
+            note_pitch,
+            durations,
+            dynamic: None,
+            articulations: Vec::new(),
+            slur: None,
+        })
+    }
+
+    /// Ties `note` through `durations`, keeping its dynamic, articulations and slur.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NewTiedNoteError::NoDurations`] if `durations` is empty.
+    pub fn from_note(
+        note: &Note,
+        durations: Vec<rhythm::Duration>,
+    ) -> Result<Self, NewTiedNoteError> {
+        Ok(Self {
+            dynamic: note.dynamic,
+            articulations: note.articulations.clone(),
+            slur: note.slur,
+            ..Self::new(note.note_pitch, durations)?
+        })
+    }
+
+    #[must_use]
+    pub fn note_pitch(&self) -> &pitch::NotePitch {
+        &self.note_pitch
+    }
+
+    /// The durations of the tied notes, in order.
+    #[must_use]
+    pub fn durations(&self) -> &[rhythm::Duration] {
+        &self.durations
+    }
+
+    /// The dynamic marked on the first of the tied notes, if any.
+    #[must_use]
+    pub fn dynamic(&self) -> Option<dynamics::Dynamic> {
+        self.dynamic
+    }
+
+    /// The articulations marked on the first of the tied notes, in the order they were added.
+    #[must_use]
+    pub fn articulations(&self) -> &[Articulation] {
+        &self.articulations
+    }
+
+    /// The tied note's place in a slur, taken as a whole.
+    #[must_use]
+    pub fn slur(&self) -> Option<Slur> {
+        self.slur
+    }
+
+    /// The tied notes as separate notes, in order. The first carries the dynamic and
+    /// articulations, and a slur starts on the first and ends on the last.
+    #[must_use]
+    pub fn notes(&self) -> Vec<Note> {
+        let last = self.durations.len().saturating_sub(1);
+        self.durations
+            .iter()
+            .enumerate()
+            .map(|(index, duration)| {
+                let mut note = Note::new(self.note_pitch, *duration);
+                if index == 0 {
+                    note.dynamic = self.dynamic;
+                    note.articulations.clone_from(&self.articulations);
+                }
+                note.slur = self.slur.map(|slur| match slur {
+                    Slur::Start if index == 0 => Slur::Start,
+                    Slur::End if index == last => Slur::End,
+                    _ => Slur::Middle,
+                });
+                note
+            })
+            .collect()
+    }
+
+    /// The single note that sounds, lasting the tied durations combined.
+    #[must_use]
+    pub fn joined(&self) -> Note {
+        Note {
+            dynamic: self.dynamic,
+            articulations: self.articulations.clone(),
+            slur: self.slur,
+            ..Note::new(self.note_pitch, rhythm::HasDuration::duration(self))
+        }
+    }
+}
+
+impl rhythm::HasDuration for TiedNote {
+    fn duration(&self) -> rhythm::Duration {
+        self.durations.iter().sum()
+    }
+}
+
+/// Anything that can be placed in a [`crate::composition::Timeline`] of a melody or part.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event {
+    Note(Note),
+    Rest(Rest),
+    Chord(ChordEvent),
+    TiedNote(TiedNote),
+}
+
+impl Event {
+    #[must_use]
+    pub fn is_rest(&self) -> bool {
+        matches!(self, Self::Rest(_))
+    }
+
+    /// The pitches that sound during the event, lowest first; none for a rest.
+    #[must_use]
+    pub fn note_pitches(&self) -> Vec<pitch::NotePitch> {
+        match self {
+            Self::Note(note) => vec![note.note_pitch],
+            Self::Rest(_) => Vec::new(),
+            Self::Chord(chord) => chord.chord.note_pitches().iter().copied().collect(),
+            Self::TiedNote(tied_note) => vec![tied_note.note_pitch],
+        }
+    }
+
+    /// The notes that sound during the event, with tied notes joined into one.
+    #[must_use]
+    pub fn sounding_notes(&self) -> Vec<Note> {
+        match self {
+            Self::Note(note) => vec![note.clone()],
+            Self::Rest(_) => Vec::new(),
+            Self::Chord(chord) => chord.notes(),
+            Self::TiedNote(tied_note) => vec![tied_note.joined()],
+        }
+    }
+}
+
+impl rhythm::HasDuration for Event {
+    fn duration(&self) -> rhythm::Duration {
+        match self {
+            Self::Note(note) => note.duration,
+            Self::Rest(rest) => rest.duration,
+            Self::Chord(chord) => chord.duration,
+            Self::TiedNote(tied_note) => rhythm::HasDuration::duration(tied_note),
+        }
+    }
+}
+
+impl From<Note> for Event {
+    fn from(note: Note) -> Self {
+        Self::Note(note)
+    }
+}
+
+impl From<Rest> for Event {
+    fn from(rest: Rest) -> Self {
+        Self::Rest(rest)
+    }
+}
+
+impl From<ChordEvent> for Event {
+    fn from(chord: ChordEvent) -> Self {
+        Self::Chord(chord)
+    }
+}
+
+impl From<TiedNote> for Event {
+    fn from(tied_note: TiedNote) -> Self {
+        Self::TiedNote(tied_note)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::composition::Timeline;
+    use crate::harmony::Chord;
+    use crate::pitch::{NotePitch, NotePitchClass};
+    use crate::rhythm::{Duration, HasDuration};
+
+    use super::*;
+
+    #[test]
+    fn melody_with_rests_chords_and_ties() {
+        let quarter = Duration::new(1, 4).unwrap();
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        let e4 = NotePitch::new(NotePitchClass::E, 4);
+        let g4 = NotePitch::new(NotePitchClass::G, 4);
+
+        let tied = TiedNote::new(g4, vec![quarter, quarter / 2]).unwrap();
+        assert_eq!(tied.duration(), Duration::new(3, 8).unwrap());
+        assert_eq!(tied.joined(), Note::new(g4, Duration::new(3, 8).unwrap()));
+        assert_eq!(
+            TiedNote::new(g4, Vec::new()),
+            Err(NewTiedNoteError::NoDurations)
+        );
+
+        let marked = Note::new(g4, quarter)
+            .with_dynamic(dynamics::Dynamic::F)
+            .with_articulation(Articulation::Accent)
+            .with_slur(Slur::Start);
+        let tied_marked = TiedNote::from_note(&marked, vec![quarter / 2, quarter / 2]).unwrap();
+        assert_eq!(tied_marked.joined(), marked);
+        let parts = tied_marked.notes();
+        assert_eq!(parts[0].dynamic(), Some(dynamics::Dynamic::F));
+        assert_eq!(parts[0].slur(), Some(Slur::Start));
+        assert!(parts[1].articulations().is_empty());
+        assert_eq!(parts[1].slur(), Some(Slur::Middle));
+
+        let chord = ChordEvent::new(Chord::new([c4, e4, g4].into_iter().collect()), quarter);
+        let mut melody: Timeline<Event> = Timeline::new();
+        melody.insert(Duration::zero(), Note::new(c4, quarter).into());
+        melody.insert(quarter, Rest::new(quarter).into());
+        melody.insert(quarter * 2, chord.into());
+        melody.insert(quarter * 3, tied.into());
+
+        assert_eq!(melody.end(), Duration::new(9, 8).unwrap());
+        let rests: Vec<_> = melody.iter().filter(|(_, event)| event.is_rest()).collect();
+        assert_eq!(rests.len(), 1);
+        assert_eq!(
+            melody.events_at(&(quarter * 2))[0].note_pitches(),
+            vec![c4, e4, g4]
+        );
+        assert_eq!(
+            melody.events_at(&(quarter * 2))[0].sounding_notes().len(),
+            3
+        );
+        assert!(melody.events_at(&quarter)[0].sounding_notes().is_empty());
+    }
 }
@@ -1,4 +1,4 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, num::NonZeroU16, str::FromStr};
 
 use crate::interval;
 
@@ -7,16 +7,293 @@ use serde::{Deserialize, Serialize};
 
 pub type Pitch = f64;
 
+/// A distance between pitches in hundredths of an equal-tempered semitone.
+pub type Cents = f64;
+
+/// The cents from `from` up to `to`, which are negative if `to` is lower.
+#[must_use]
+pub fn cents_between(from: Pitch, to: Pitch) -> Cents {
+    1200.0 * (to / from).log2()
+}
+
+/// The pitch `cents` above `pitch`, or below it for negative cents.
+#[must_use]
+pub fn add_cents(pitch: Pitch, cents: Cents) -> Pitch {
+    pitch * 2f64.powf(cents / 1200.0)
+}
+
+/// Where a tuning system is anchored: the note that sounds at a given pitch, and the pitch class
+/// its temperament is laid out from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TuningReference {
+    note: NotePitch,
+    pitch: Pitch,
+    tonic: NotePitchClass,
+}
+
+impl TuningReference {
+    #[must_use]
+    pub fn new(note: NotePitch, pitch: Pitch, tonic: NotePitchClass) -> Self {
+        Self { note, pitch, tonic }
+    }
+
+    #[must_use]
+    pub fn note(&self) -> NotePitch {
+        self.note
+    }
+
+    #[must_use]
+    pub fn pitch(&self) -> Pitch {
+        self.pitch
+    }
+
+    /// The pitch class the temperament is built from. Just intonation is pure above it, the
+    /// chains of fifths of Pythagorean tuning and meantone run from three fifths below it to
+    /// eight above, and the well temperaments are transposed so that their C falls on it.
+    #[must_use]
+    pub fn tonic(&self) -> NotePitchClass {
+        self.tonic
+    }
+}
+
+/// A4 sounding at 440 Hz, with temperaments laid out from C.
+impl Default for TuningReference {
+    fn default() -> Self {
+        Self::new(
+            NotePitch::new(NotePitchClass::A, 4),
+            A4_PITCH_ISO_16,
+            NotePitchClass::C,
+        )
+    }
+}
+
+/// A way of giving note pitches frequencies, such as a [`TuningSystem`] or a tuning loaded from
+/// Scala files.
+pub trait Tuning {
+    /// The note and frequency the tuning is anchored at.
+    fn reference(&self) -> TuningReference;
+
+    /// The cents from the reference note up to `note_pitch`, which are negative below it, or
+    /// `None` if the tuning gives it no pitch.
+    fn cents_from_reference(&self, note_pitch: &NotePitch) -> Option<Cents>;
+
+    /// The frequency of `note_pitch`, or `None` if the tuning gives it no pitch.
+    fn pitch(&self, note_pitch: &NotePitch) -> Option<Pitch> {
+        self.cents_from_reference(note_pitch)
+            .map(|cents| add_cents(self.reference().pitch, cents))
+    }
+
+    /// The frequency of `note_pitch`, which tunings may tell apart from its enharmonic
+    /// equivalents.
+    fn spelled_pitch(&self, note_pitch: &SpelledNotePitch) -> Option<Pitch> {
+        self.pitch(&note_pitch.note_pitch())
+    }
+
+    /// The note pitch that sounds closest to `frequency` in this tuning and how far the frequency
+    /// is from it, or `None` if the frequency is not positive and finite.
+    ///
+    /// Only note pitches within a tritone of the equal-tempered estimate are considered, which is
+    /// always enough for the built-in tunings. Note pitches without a pitch in the tuning are
+    /// skipped.
+    fn nearest_note_pitch(&self, frequency: Pitch) -> Option<NearestNotePitch> {
+        if !frequency.is_finite() || frequency <= 0.0 {
+            return None;
+        }
+        let reference = self.reference();
+        let estimate = (cents_between(reference.pitch, frequency) / 100.0).round();
+        if estimate.abs() > f64::from(i16::MAX) {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let estimate = estimate as i32;
+        (estimate - 6..=estimate + 6)
+            .filter_map(|semitones| {
+                let note_pitch =
+                    interval::SemitoneInterval::new(semitones).apply_to_note_pitch(&reference.note);
+                let cents = cents_between(self.pitch(&note_pitch)?, frequency);
+                Some(NearestNotePitch { note_pitch, cents })
+            })
+            .min_by(|a, b| a.cents.abs().total_cmp(&b.cents.abs()))
+    }
+
+    /// [`Tuning::nearest_note_pitch`] for every frequency in `frequencies`.
+    fn nearest_note_pitches(&self, frequencies: &[Pitch]) -> Vec<Option<NearestNotePitch>> {
+        frequencies
+            .iter()
+            .map(|frequency| self.nearest_note_pitch(*frequency))
+            .collect()
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[allow(clippy::derive_partial_eq_without_eq)]
 pub enum TuningSystem {
+    /// Twelve-tone equal temperament with A4 at 440 Hz.
     #[default]
     EqualTempered,
+    Pythagorean(TuningReference),
+    QuarterCommaMeantone(TuningReference),
+    /// Five-limit just intonation.
+    JustIntonation(TuningReference),
+    WerckmeisterIII(TuningReference),
+    KirnbergerIII(TuningReference),
+    Vallotti(TuningReference),
+    /// An equal division of the octave, such as 19-TET or 31-TET. Twelve-tone pitches are mapped
+    /// onto it along the chain of fifths, as in meantone.
+    Edo {
+        divisions: NonZeroU16,
+        reference: TuningReference,
+    },
+}
+
+impl TuningSystem {
+    #[must_use]
+    pub fn reference(&self) -> TuningReference {
+        match self {
+            Self::EqualTempered => TuningReference::default(),
+            Self::Pythagorean(reference)
+            | Self::QuarterCommaMeantone(reference)
+            | Self::JustIntonation(reference)
+            | Self::WerckmeisterIII(reference)
+            | Self::KirnbergerIII(reference)
+            | Self::Vallotti(reference)
+            | Self::Edo { reference, .. } => *reference,
+        }
+    }
+
+    /// The size of the fifth for tunings generated by a chain of fifths.
+    fn fifth_cents(&self) -> Option<f64> {
+        match self {
+            Self::EqualTempered => Some(700.0),
+            Self::Pythagorean(_) => Some(1200.0 * 1.5f64.log2()),
+            Self::QuarterCommaMeantone(_) => Some(300.0 * 5f64.log2()),
+            Self::Edo { divisions, .. } => {
+                let divisions = f64::from(divisions.get());
+                Some((divisions * 1.5f64.log2()).round() * 1200.0 / divisions)
+            }
+            _ => None,
+        }
+    }
+
+    /// The cents above the tonic of each pitch class, counted in semitones above the tonic.
+    pub(crate) fn octave_cents(&self) -> [f64; 12] {
+        if let Some(fifth) = self.fifth_cents() {
+            return std::array::from_fn(|semitones| {
+                let fifths = CHAIN_OF_FIFTHS
+                    .into_iter()
+                    .find(|fifths| (fifths * 7).rem_euclid(12) as usize == semitones)
+                    .unwrap_or_else(|| unreachable!("the chain covers every pitch class"));
+                (f64::from(fifths) * fifth).rem_euclid(1200.0)
+            });
+        }
+        match self {
+            Self::JustIntonation(_) => JUST_INTONATION_RATIOS.map(|(numerator, denominator)| {
+                1200.0 * (f64::from(numerator) / f64::from(denominator)).log2()
+            }),
+            Self::WerckmeisterIII(_) => WERCKMEISTER_III_CENTS,
+            Self::KirnbergerIII(_) => KIRNBERGER_III_CENTS,
+            Self::Vallotti(_) => VALLOTTI_CENTS,
+            _ => unreachable!("every other tuning is a chain of fifths"),
+        }
+    }
+
+    /// The cents from the reference note up to `note_pitch`, which are negative below it.
+    #[must_use]
+    pub fn cents_from_reference(&self, note_pitch: &NotePitch) -> Cents {
+        let reference = self.reference();
+        let octave_cents = self.octave_cents();
+        let cents = |note_pitch: &NotePitch| {
+            let semitones = interval::SemitoneInterval::new_from_note_pitches(
+                &NotePitch::new(reference.tonic, 0),
+                note_pitch,
+            )
+            .semitones();
+            f64::from(semitones.div_euclid(12)) * 1200.0
+                + octave_cents[semitones.rem_euclid(12) as usize]
+        };
+        cents(note_pitch) - cents(&reference.note)
+    }
+}
+
+/// Tunings generated by a chain of fifths follow the spelling, so that for example G# and Ab
+/// differ in meantone; other tunings treat enharmonic spellings alike.
+impl Tuning for TuningSystem {
+    fn reference(&self) -> TuningReference {
+        TuningSystem::reference(self)
+    }
+
+    fn cents_from_reference(&self, note_pitch: &NotePitch) -> Option<Cents> {
+        Some(TuningSystem::cents_from_reference(self, note_pitch))
+    }
+
+    fn pitch(&self, note_pitch: &NotePitch) -> Option<Pitch> {
+        if *self == Self::EqualTempered {
+            let semitones_from_a4 = interval::SemitoneInterval::new_from_note_pitches(
+                &NotePitch {
+                    class: NotePitchClass::A,
+                    octave: 4,
+                },
+                note_pitch,
+            )
+            .semitones();
+
+            // https://pages.mtu.edu/~suits/NoteFreqCalcs.html
+            return Some(A4_PITCH_ISO_16 * EQUAL_TEMPERED_SEMITONE_FACTOR.powi(semitones_from_a4));
+        }
+        Some(add_cents(
+            self.reference().pitch,
+            TuningSystem::cents_from_reference(self, note_pitch),
+        ))
+    }
+
+    fn spelled_pitch(&self, note_pitch: &SpelledNotePitch) -> Option<Pitch> {
+        let Some(fifth) = self.fifth_cents() else {
+            return self.pitch(&note_pitch.note_pitch());
+        };
+        let reference = self.reference();
+        let from = SpelledNotePitch::from(reference.note);
+        let steps = note_pitch.diatonic_steps() - from.diatonic_steps();
+        let semitones = note_pitch.semitones() - from.semitones();
+        // a fifth is 4 steps and 7 semitones, an octave 7 steps and 12 semitones
+        let fifths = 7 * semitones - 12 * steps;
+        let octaves = 7 * steps - 4 * semitones;
+        let cents = f64::from(fifths) * fifth + f64::from(octaves) * 1200.0;
+        Some(reference.pitch * 2f64.powf(cents / 1200.0))
+    }
+}
+
+/// A note pitch found for a frequency, together with how far the frequency is from it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NearestNotePitch {
+    note_pitch: NotePitch,
+    cents: Cents,
+}
+
+impl NearestNotePitch {
+    #[must_use]
+    pub fn note_pitch(&self) -> NotePitch {
+        self.note_pitch
+    }
+
+    /// The cents from the note pitch up to the frequency, which are negative if it is flat.
+    #[must_use]
+    pub fn cents(&self) -> Cents {
+        self.cents
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
 pub trait ToPitch {
-    fn to_pitch_using_tuning(&self, tuning: TuningSystem) -> Pitch;
+    /// The frequency in `tuning`, or `None` if the tuning gives this pitch none.
+    fn to_pitch_in(&self, tuning: &dyn Tuning) -> Option<Pitch>;
+
+    fn to_pitch_using_tuning(&self, tuning: TuningSystem) -> Pitch {
+        self.to_pitch_in(&tuning)
+            .unwrap_or_else(|| unreachable!("tuning systems give every note pitch a pitch"))
+    }
 
     fn to_pitch(&self) -> Pitch {
         self.to_pitch_using_tuning(TuningSystem::EqualTempered)
@@ -40,6 +317,29 @@ pub enum NotePitchClass {
     B = 11,
 }
 
+impl NotePitchClass {
+    /// Returns the pitch class that is `semitones` above C, wrapping around the octave in either
+    /// direction.
+    #[must_use]
+    pub fn from_semitones(semitones: interval::Semitones) -> Self {
+        match semitones.rem_euclid(12) {
+            0 => Self::C,
+            1 => Self::Cs,
+            2 => Self::D,
+            3 => Self::Ds,
+            4 => Self::E,
+            5 => Self::F,
+            6 => Self::Fs,
+            7 => Self::G,
+            8 => Self::Gs,
+            9 => Self::A,
+            10 => Self::As,
+            11 => Self::B,
+            _ => unreachable!("rem_euclid(12) is always in 0..12"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct IntDoesNotMatchEnum;
 
@@ -97,6 +397,32 @@ impl NotePitch {
     pub fn octave(&self) -> interval::Octave {
         self.octave
     }
+
+    /// Semitones above C0.
+    #[must_use]
+    pub fn semitones(&self) -> interval::Semitones {
+        self.octave * 12 + self.class as i32
+    }
+
+    /// Creates the pitch for a MIDI note number, where 60 is middle C (C4), or `None` if the
+    /// number is above 127.
+    #[must_use]
+    pub fn from_midi_number(number: u8) -> Option<Self> {
+        let semitones = i32::from(number);
+        (number <= 127).then(|| Self {
+            class: NotePitchClass::from_semitones(semitones),
+            octave: semitones.div_euclid(12) - 1,
+        })
+    }
+
+    /// The MIDI note number of this pitch, where 60 is middle C (C4), or `None` if the pitch lies
+    /// outside of the MIDI range C-1..=G9.
+    #[must_use]
+    pub fn midi_number(&self) -> Option<u8> {
+        u8::try_from(self.semitones() + 12)
+            .ok()
+            .filter(|number| *number <= 127)
+    }
 }
 
 impl Ord for NotePitch {
@@ -117,27 +443,478 @@ impl PartialOrd for NotePitch {
 }
 
 impl ToPitch for NotePitch {
-    fn to_pitch_using_tuning(&self, tuning: TuningSystem) -> Pitch {
-        match tuning {
-            TuningSystem::EqualTempered => {
-                let semitones_from_a4 = interval::SemitoneInterval::new_from_note_pitches(
-                    &NotePitch {
-                        class: NotePitchClass::A,
-                        octave: 4,
-                    },
-                    self,
-                )
-                .semitones();
-
-                // https://pages.mtu.edu/~suits/NoteFreqCalcs.html
-                A4_PITCH_ISO_16 * EQUAL_TEMPERED_SEMITONE_FACTOR.powi(semitones_from_a4)
-            }
+    fn to_pitch_in(&self, tuning: &dyn Tuning) -> Option<Pitch> {
+        tuning.pitch(self)
+    }
+}
+
+/// The seven natural note names.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Letter {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Letter {
+    pub const ALL: [Letter; 7] = [
+        Letter::C,
+        Letter::D,
+        Letter::E,
+        Letter::F,
+        Letter::G,
+        Letter::A,
+        Letter::B,
+    ];
+
+    /// The letter `steps` diatonic steps above C, wrapping around the octave in either direction.
+    #[must_use]
+    pub fn from_steps(steps: i32) -> Self {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        Self::ALL[steps.rem_euclid(7) as usize]
+    }
+
+    /// The number of diatonic steps from C up to this letter.
+    #[must_use]
+    pub fn steps(self) -> i32 {
+        self as i32
+    }
+
+    #[must_use]
+    pub fn natural_class(self) -> NotePitchClass {
+        match self {
+            Self::C => NotePitchClass::C,
+            Self::D => NotePitchClass::D,
+            Self::E => NotePitchClass::E,
+            Self::F => NotePitchClass::F,
+            Self::G => NotePitchClass::G,
+            Self::A => NotePitchClass::A,
+            Self::B => NotePitchClass::B,
+        }
+    }
+
+    #[must_use]
+    pub fn to_char(self) -> char {
+        match self {
+            Self::C => 'C',
+            Self::D => 'D',
+            Self::E => 'E',
+            Self::F => 'F',
+            Self::G => 'G',
+            Self::A => 'A',
+            Self::B => 'B',
+        }
+    }
+
+    /// Parses an upper or lower case letter name.
+    #[must_use]
+    pub fn from_char(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'C' => Some(Self::C),
+            'D' => Some(Self::D),
+            'E' => Some(Self::E),
+            'F' => Some(Self::F),
+            'G' => Some(Self::G),
+            'A' => Some(Self::A),
+            'B' => Some(Self::B),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Accidental {
+    DoubleFlat = -2,
+    Flat = -1,
+    Natural = 0,
+    Sharp = 1,
+    DoubleSharp = 2,
+}
+
+impl Accidental {
+    /// The number of semitones the accidental raises (or, when negative, lowers) a letter by.
+    #[must_use]
+    pub fn semitones(self) -> interval::Semitones {
+        self as i32
+    }
+
+    #[must_use]
+    pub fn from_semitones(semitones: interval::Semitones) -> Option<Self> {
+        match semitones {
+            -2 => Some(Self::DoubleFlat),
+            -1 => Some(Self::Flat),
+            0 => Some(Self::Natural),
+            1 => Some(Self::Sharp),
+            2 => Some(Self::DoubleSharp),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Accidental {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::DoubleFlat => "bb",
+            Self::Flat => "b",
+            Self::Natural => "",
+            Self::Sharp => "#",
+            Self::DoubleSharp => "##",
+        })
+    }
+}
+
+/// A pitch class written as a letter and an accidental, so that for example C# and Db are
+/// different values even though they share a [`NotePitchClass`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpelledNotePitchClass {
+    letter: Letter,
+    accidental: Accidental,
+}
+
+impl SpelledNotePitchClass {
+    #[must_use]
+    pub fn new(letter: Letter, accidental: Accidental) -> Self {
+        Self { letter, accidental }
+    }
+
+    #[must_use]
+    pub fn natural(letter: Letter) -> Self {
+        Self::new(letter, Accidental::Natural)
+    }
+
+    #[must_use]
+    pub fn letter(&self) -> Letter {
+        self.letter
+    }
+
+    #[must_use]
+    pub fn accidental(&self) -> Accidental {
+        self.accidental
+    }
+
+    /// Spells `class` with `letter`, or returns `None` if that would need more than a double
+    /// accidental.
+    #[must_use]
+    pub fn with_letter(class: NotePitchClass, letter: Letter) -> Option<Self> {
+        let difference = (class as i32 - letter.natural_class() as i32 + 6).rem_euclid(12) - 6;
+        Accidental::from_semitones(difference).map(|accidental| Self::new(letter, accidental))
+    }
+
+    /// Spells `class` using a natural or a sharp, which matches the naming of [`NotePitchClass`].
+    #[must_use]
+    pub fn sharp_spelling(class: NotePitchClass) -> Self {
+        Self::spelling_with_accidental(class, Accidental::Sharp)
+    }
+
+    /// Spells `class` using a natural or a flat.
+    #[must_use]
+    pub fn flat_spelling(class: NotePitchClass) -> Self {
+        Self::spelling_with_accidental(class, Accidental::Flat)
+    }
+
+    fn spelling_with_accidental(class: NotePitchClass, accidental: Accidental) -> Self {
+        let spellings = Self::spellings(class);
+        let find = |wanted| {
+            spellings
+                .iter()
+                .copied()
+                .find(|spelled| spelled.accidental() == wanted)
+        };
+        find(Accidental::Natural)
+            .or_else(|| find(accidental))
+            .unwrap_or(spellings[0])
+    }
+
+    /// Every spelling of `class` that uses at most a double accidental.
+    #[must_use]
+    pub fn spellings(class: NotePitchClass) -> Vec<Self> {
+        Letter::ALL
+            .iter()
+            .filter_map(|letter| Self::with_letter(class, *letter))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn note_pitch_class(&self) -> NotePitchClass {
+        NotePitchClass::from_semitones(
+            self.letter.natural_class() as i32 + self.accidental.semitones(),
+        )
+    }
+
+    /// The position of the spelling on the line of fifths, counting C as zero, G as one and F as
+    /// minus one; sharps add seven and flats subtract seven.
+    #[must_use]
+    pub fn fifths(&self) -> i32 {
+        let letter_fifths = (self.letter.steps() * 2 + 1).rem_euclid(7) - 1;
+        letter_fifths + 7 * self.accidental.semitones()
+    }
+
+    /// The spelling at position `fifths` on the line of fifths; see
+    /// [`SpelledNotePitchClass::fifths`]. Returns `None` beyond double accidentals.
+    #[must_use]
+    pub fn from_fifths(fifths: i32) -> Option<Self> {
+        let letter = Letter::from_steps(((fifths + 1).rem_euclid(7) - 1) * 4);
+        let accidental = Accidental::from_semitones((fifths + 1).div_euclid(7))?;
+        Some(Self::new(letter, accidental))
+    }
+}
+
+impl From<NotePitchClass> for SpelledNotePitchClass {
+    fn from(class: NotePitchClass) -> Self {
+        Self::sharp_spelling(class)
+    }
+}
+
+impl From<SpelledNotePitchClass> for NotePitchClass {
+    fn from(spelled: SpelledNotePitchClass) -> Self {
+        spelled.note_pitch_class()
+    }
+}
+
+impl Display for SpelledNotePitchClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.letter.to_char(), self.accidental)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseSpelledPitchError {
+    Empty,
+    InvalidLetter,
+    InvalidAccidental,
+    InvalidOctave,
+}
+
+impl Display for ParseSpelledPitchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Empty => "empty pitch name",
+            Self::InvalidLetter => "pitch name does not start with a letter from A to G",
+            Self::InvalidAccidental => "invalid accidental",
+            Self::InvalidOctave => "invalid octave",
+        })
+    }
+}
+
+impl Error for ParseSpelledPitchError {}
+
+impl SpelledNotePitchClass {
+    /// Parses a spelled pitch class at the start of `text`, returning it and the number of bytes
+    /// it took up. Accidentals may be written as `#`, `b`, `x` (double sharp), `##` or `bb`.
+    pub(crate) fn parse_prefix(text: &str) -> Result<(Self, usize), ParseSpelledPitchError> {
+        let mut chars = text.chars();
+        let letter = chars.next().ok_or(ParseSpelledPitchError::Empty)?;
+        let letter = Letter::from_char(letter).ok_or(ParseSpelledPitchError::InvalidLetter)?;
+        let rest = &text[1..];
+        let (accidental, length) = if rest.starts_with("##") {
+            (Accidental::DoubleSharp, 2)
+        } else if rest.starts_with("bb") {
+            (Accidental::DoubleFlat, 2)
+        } else if rest.starts_with('x') {
+            (Accidental::DoubleSharp, 1)
+        } else if rest.starts_with('#') {
+            (Accidental::Sharp, 1)
+        } else if rest.starts_with('b') {
+            (Accidental::Flat, 1)
+        } else {
+            (Accidental::Natural, 0)
+        };
+        Ok((Self::new(letter, accidental), 1 + length))
+    }
+}
+
+impl FromStr for SpelledNotePitchClass {
+    type Err = ParseSpelledPitchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (spelled, length) = Self::parse_prefix(s)?;
+        if length != s.len() {
+            return Err(ParseSpelledPitchError::InvalidAccidental);
         }
+        Ok(spelled)
+    }
+}
+
+/// A [`NotePitch`] that keeps its spelling.
+///
+/// The octave number belongs to the letter, as in scientific pitch notation: B#3 sounds the same
+/// as C4 and Cb4 sounds the same as B3.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpelledNotePitch {
+    class: SpelledNotePitchClass,
+    octave: interval::Octave,
+}
+
+impl SpelledNotePitch {
+    #[must_use]
+    pub fn new(class: SpelledNotePitchClass, octave: interval::Octave) -> Self {
+        Self { class, octave }
+    }
+
+    #[must_use]
+    pub fn class(&self) -> SpelledNotePitchClass {
+        self.class
+    }
+
+    #[must_use]
+    pub fn octave(&self) -> interval::Octave {
+        self.octave
+    }
+
+    #[must_use]
+    pub fn letter(&self) -> Letter {
+        self.class.letter()
+    }
+
+    #[must_use]
+    pub fn accidental(&self) -> Accidental {
+        self.class.accidental()
+    }
+
+    /// Diatonic steps above C0, ignoring the accidental.
+    #[must_use]
+    pub fn diatonic_steps(&self) -> i32 {
+        self.octave * 7 + self.class.letter().steps()
+    }
+
+    /// Sounding semitones above C0.
+    #[must_use]
+    pub fn semitones(&self) -> interval::Semitones {
+        self.octave * 12
+            + self.class.letter().natural_class() as i32
+            + self.class.accidental().semitones()
+    }
+
+    /// Spells the pitch that is `diatonic_steps` above C0 so that it sounds `semitones` above C0,
+    /// or returns `None` if that would need more than a double accidental.
+    #[must_use]
+    pub fn from_steps_and_semitones(
+        diatonic_steps: i32,
+        semitones: interval::Semitones,
+    ) -> Option<Self> {
+        let letter = Letter::from_steps(diatonic_steps);
+        let octave = diatonic_steps.div_euclid(7);
+        let natural = octave * 12 + letter.natural_class() as i32;
+        let accidental = Accidental::from_semitones(semitones - natural)?;
+        Some(Self::new(
+            SpelledNotePitchClass::new(letter, accidental),
+            octave,
+        ))
+    }
+
+    /// Spells `note_pitch` using `letter` in whichever octave keeps it sounding the same, or
+    /// returns `None` if that would need more than a double accidental.
+    #[must_use]
+    pub fn with_letter(note_pitch: &NotePitch, letter: Letter) -> Option<Self> {
+        let semitones = note_pitch.semitones();
+        (note_pitch.octave() - 1..=note_pitch.octave() + 1).find_map(|octave| {
+            Self::from_steps_and_semitones(octave * 7 + letter.steps(), semitones)
+        })
+    }
+
+    #[must_use]
+    pub fn note_pitch(&self) -> NotePitch {
+        let semitones = self.semitones();
+        NotePitch::new(
+            NotePitchClass::from_semitones(semitones),
+            semitones.div_euclid(12),
+        )
+    }
+}
+
+impl From<NotePitch> for SpelledNotePitch {
+    fn from(note_pitch: NotePitch) -> Self {
+        Self::new(note_pitch.class().into(), note_pitch.octave())
+    }
+}
+
+impl From<SpelledNotePitch> for NotePitch {
+    fn from(spelled: SpelledNotePitch) -> Self {
+        spelled.note_pitch()
+    }
+}
+
+/// Orders by sounding pitch, then by letter, so that B#3 < C4 < Dbb4.
+impl Ord for SpelledNotePitch {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.semitones(), self.diatonic_steps()).cmp(&(other.semitones(), other.diatonic_steps()))
+    }
+}
+
+impl PartialOrd for SpelledNotePitch {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for SpelledNotePitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.class, self.octave)
+    }
+}
+
+impl FromStr for SpelledNotePitch {
+    type Err = ParseSpelledPitchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, length) = SpelledNotePitchClass::parse_prefix(s)?;
+        let octave = s[length..]
+            .parse()
+            .map_err(|_| ParseSpelledPitchError::InvalidOctave)?;
+        Ok(Self::new(class, octave))
+    }
+}
+
+impl ToPitch for SpelledNotePitch {
+    fn to_pitch_in(&self, tuning: &dyn Tuning) -> Option<Pitch> {
+        tuning.spelled_pitch(self)
     }
 }
 
 const A4_PITCH_ISO_16: f64 = 440.0;
 
+/// The fifths above the tonic, from a minor third to an augmented fifth, that make up the twelve
+/// notes of tunings generated by a chain of fifths.
+const CHAIN_OF_FIFTHS: [i32; 12] = [-3, -2, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+pub(crate) const JUST_INTONATION_RATIOS: [(u32, u32); 12] = [
+    (1, 1),
+    (16, 15),
+    (9, 8),
+    (6, 5),
+    (5, 4),
+    (4, 3),
+    (45, 32),
+    (3, 2),
+    (8, 5),
+    (5, 3),
+    (9, 5),
+    (15, 8),
+];
+
+const WERCKMEISTER_III_CENTS: [f64; 12] = [
+    0.0, 90.225, 192.18, 294.135, 390.225, 498.045, 588.27, 696.09, 792.18, 888.27, 996.09, 1092.18,
+];
+
+const KIRNBERGER_III_CENTS: [f64; 12] = [
+    0.0, 90.225, 193.157, 294.135, 386.314, 498.045, 590.224, 696.578, 792.18, 889.735, 996.09,
+    1088.269,
+];
+
+const VALLOTTI_CENTS: [f64; 12] = [
+    0.0, 94.135, 196.09, 298.045, 392.18, 501.955, 592.18, 698.045, 796.09, 894.135, 1000.0,
+    1090.225,
+];
+
 // https://pages.mtu.edu/~suits/NoteFreqCalcs.html
 const EQUAL_TEMPERED_SEMITONE_FACTOR: f64 = 1.059_463_094_36;
 
@@ -186,4 +963,245 @@ mod tests {
         };
         assert!((gs8.to_pitch() - 6644.88).abs() < 0.05);
     }
+
+    #[test]
+    fn note_pitch_midi_numbers() {
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        assert_eq!(c4.midi_number(), Some(60));
+        assert_eq!(NotePitch::from_midi_number(60), Some(c4));
+        assert_eq!(
+            NotePitch::from_midi_number(0),
+            Some(NotePitch::new(NotePitchClass::C, -1))
+        );
+        assert_eq!(NotePitch::from_midi_number(128), None);
+        assert_eq!(c4.semitones(), 48);
+        assert_eq!(NotePitch::new(NotePitchClass::B, -1).semitones(), -1);
+        assert_eq!(
+            NotePitch::new(NotePitchClass::G, 9).midi_number(),
+            Some(127)
+        );
+        assert_eq!(NotePitch::new(NotePitchClass::Gs, 9).midi_number(), None);
+        assert_eq!(NotePitch::new(NotePitchClass::B, -2).midi_number(), None);
+    }
+
+    #[test]
+    fn spelled_note_pitch_class_conversions() {
+        let c_sharp: SpelledNotePitchClass = "C#".parse().unwrap();
+        let d_flat: SpelledNotePitchClass = "Db".parse().unwrap();
+        assert_ne!(c_sharp, d_flat);
+        assert_eq!(c_sharp.note_pitch_class(), NotePitchClass::Cs);
+        assert_eq!(d_flat.note_pitch_class(), NotePitchClass::Cs);
+
+        assert_eq!(
+            SpelledNotePitchClass::from(NotePitchClass::As).to_string(),
+            "A#"
+        );
+        assert_eq!(
+            SpelledNotePitchClass::flat_spelling(NotePitchClass::As).to_string(),
+            "Bb"
+        );
+        assert_eq!(
+            SpelledNotePitchClass::sharp_spelling(NotePitchClass::C).to_string(),
+            "C"
+        );
+        assert_eq!(
+            "Cbb"
+                .parse::<SpelledNotePitchClass>()
+                .unwrap()
+                .note_pitch_class(),
+            NotePitchClass::As
+        );
+        assert_eq!(
+            "Fx".parse::<SpelledNotePitchClass>().unwrap().to_string(),
+            "F##"
+        );
+        assert_eq!(
+            SpelledNotePitchClass::spellings(NotePitchClass::Gs).len(),
+            2
+        );
+        assert_eq!(
+            "H".parse::<SpelledNotePitchClass>(),
+            Err(ParseSpelledPitchError::InvalidLetter)
+        );
+    }
+
+    #[test]
+    fn spelled_note_pitch_class_line_of_fifths() {
+        for fifths in -15..=19 {
+            let spelled = SpelledNotePitchClass::from_fifths(fifths).unwrap();
+            assert_eq!(spelled.fifths(), fifths);
+        }
+        assert_eq!(
+            SpelledNotePitchClass::from_fifths(6).unwrap().to_string(),
+            "F#"
+        );
+        assert_eq!(
+            SpelledNotePitchClass::from_fifths(-2).unwrap().to_string(),
+            "Bb"
+        );
+        assert_eq!(SpelledNotePitchClass::from_fifths(20), None);
+    }
+
+    #[test]
+    fn spelled_note_pitch_octaves_follow_the_letter() {
+        let b_sharp_3: SpelledNotePitch = "B#3".parse().unwrap();
+        let c_flat_4: SpelledNotePitch = "Cb4".parse().unwrap();
+
+        assert_eq!(b_sharp_3.note_pitch(), NotePitch::new(NotePitchClass::C, 4));
+        assert_eq!(c_flat_4.note_pitch(), NotePitch::new(NotePitchClass::B, 3));
+        assert!(c_flat_4 < b_sharp_3);
+
+        let b3 = NotePitch::new(NotePitchClass::B, 3);
+        assert_eq!(
+            SpelledNotePitch::with_letter(&b3, Letter::C),
+            Some(c_flat_4)
+        );
+        assert_eq!(SpelledNotePitch::from(b3).to_string(), "B3");
+        assert!((b_sharp_3.to_pitch() - 261.63).abs() < 0.05);
+    }
+
+    fn assert_close(actual: Pitch, expected: Pitch) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn historical_tunings() {
+        let reference = TuningReference::default();
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        let e4 = NotePitch::new(NotePitchClass::E, 4);
+        let g4 = NotePitch::new(NotePitchClass::G, 4);
+        let a4 = NotePitch::new(NotePitchClass::A, 4);
+
+        for tuning in [
+            TuningSystem::Pythagorean(reference),
+            TuningSystem::QuarterCommaMeantone(reference),
+            TuningSystem::JustIntonation(reference),
+            TuningSystem::WerckmeisterIII(reference),
+            TuningSystem::KirnbergerIII(reference),
+            TuningSystem::Vallotti(reference),
+        ] {
+            assert_close(a4.to_pitch_using_tuning(tuning), 440.0);
+            assert_close(
+                NotePitch::new(NotePitchClass::A, 3).to_pitch_using_tuning(tuning),
+                220.0,
+            );
+        }
+
+        let pythagorean = TuningSystem::Pythagorean(reference);
+        assert_close(e4.to_pitch_using_tuning(pythagorean), 330.0);
+        assert_close(
+            g4.to_pitch_using_tuning(pythagorean) / c4.to_pitch_using_tuning(pythagorean),
+            1.5,
+        );
+
+        let meantone = TuningSystem::QuarterCommaMeantone(reference);
+        assert_close(
+            e4.to_pitch_using_tuning(meantone) / c4.to_pitch_using_tuning(meantone),
+            1.25,
+        );
+
+        let just = TuningSystem::JustIntonation(reference);
+        assert_close(c4.to_pitch_using_tuning(just), 264.0);
+        assert_close(e4.to_pitch_using_tuning(just), 330.0);
+
+        let werckmeister = TuningSystem::WerckmeisterIII(reference);
+        assert_close(
+            werckmeister.cents_from_reference(&g4) - werckmeister.cents_from_reference(&c4),
+            696.09,
+        );
+    }
+
+    #[test]
+    fn tuning_reference_note_pitch_and_tonic() {
+        let c4 = NotePitch::new(NotePitchClass::C, 4);
+        let d4 = NotePitch::new(NotePitchClass::D, 4);
+        let g4 = NotePitch::new(NotePitchClass::G, 4);
+        let d5 = NotePitch::new(NotePitchClass::D, 5);
+
+        let baroque = TuningReference::new(c4, 256.0, NotePitchClass::C);
+        let just = TuningSystem::JustIntonation(baroque);
+        assert_close(c4.to_pitch_using_tuning(just), 256.0);
+        assert_close(g4.to_pitch_using_tuning(just), 384.0);
+
+        // transposing the temperament moves its pure intervals to the new tonic
+        let on_g = TuningSystem::JustIntonation(TuningReference::new(g4, 392.0, NotePitchClass::G));
+        assert_close(d5.to_pitch_using_tuning(on_g), 588.0);
+        assert!((d4.to_pitch_using_tuning(just) * 2.0 - 588.0).abs() > 1.0);
+    }
+
+    #[test]
+    fn equal_divisions_of_the_octave() {
+        let reference = TuningReference::default();
+        let edo = |divisions| TuningSystem::Edo {
+            divisions: NonZeroU16::new(divisions).unwrap(),
+            reference,
+        };
+
+        let a3 = NotePitch::new(NotePitchClass::A, 3);
+        let e4 = NotePitch::new(NotePitchClass::E, 4);
+        assert!((e4.to_pitch_using_tuning(edo(12)) - e4.to_pitch()).abs() < 1e-6);
+
+        let c4: SpelledNotePitch = "C4".parse().unwrap();
+        let c_sharp4: SpelledNotePitch = "C#4".parse().unwrap();
+        let d_flat4: SpelledNotePitch = "Db4".parse().unwrap();
+        let step = |divisions: u16, from: &SpelledNotePitch, to: &SpelledNotePitch| {
+            let tuning = edo(divisions);
+            let cents = 1200.0
+                * (to.to_pitch_using_tuning(tuning) / from.to_pitch_using_tuning(tuning)).log2();
+            cents * f64::from(divisions) / 1200.0
+        };
+        assert_close(step(19, &c4, &c_sharp4), 1.0);
+        assert_close(step(19, &c4, &d_flat4), 2.0);
+        assert_close(step(31, &c4, &c_sharp4), 2.0);
+        assert_close(step(31, &c4, &d_flat4), 3.0);
+        assert_close(step(53, &c4, &"G4".parse().unwrap()), 31.0);
+
+        assert_close(a3.to_pitch_using_tuning(edo(53)), 220.0);
+    }
+
+    #[test]
+    fn cents_arithmetic() {
+        assert_close(cents_between(440.0, 880.0), 1200.0);
+        assert_close(cents_between(440.0, 220.0), -1200.0);
+        assert_close(add_cents(440.0, 700.0), 440.0 * 2f64.powf(7.0 / 12.0));
+        assert_close(add_cents(440.0, cents_between(440.0, 330.0)), 330.0);
+    }
+
+    #[test]
+    fn nearest_note_pitch_to_frequency() {
+        let equal = TuningSystem::EqualTempered;
+        let a4 = equal.nearest_note_pitch(440.0).unwrap();
+        assert_eq!(a4.note_pitch(), NotePitch::new(NotePitchClass::A, 4));
+        assert!(a4.cents().abs() < 1e-6);
+
+        let sharp = equal.nearest_note_pitch(445.0).unwrap();
+        assert_eq!(sharp.note_pitch(), NotePitch::new(NotePitchClass::A, 4));
+        assert!((sharp.cents() - 19.56).abs() < 0.01);
+
+        let flat = equal.nearest_note_pitch(27.0).unwrap();
+        assert_eq!(flat.note_pitch(), NotePitch::new(NotePitchClass::A, 0));
+        assert!(flat.cents() < 0.0);
+
+        // 330 Hz is a pure fifth below A4, so it is in tune in Pythagorean tuning
+        let pythagorean = TuningSystem::Pythagorean(TuningReference::default());
+        let e4 = pythagorean.nearest_note_pitch(330.0).unwrap();
+        assert_eq!(e4.note_pitch(), NotePitch::new(NotePitchClass::E, 4));
+        assert!(e4.cents().abs() < 1e-6);
+        assert!((equal.nearest_note_pitch(330.0).unwrap().cents() - 1.955).abs() < 0.001);
+
+        let found = equal.nearest_note_pitches(&[261.63, 0.0, f64::NAN, 1000.0]);
+        assert_eq!(
+            found[0].map(|nearest| nearest.note_pitch()),
+            Some(NotePitch::new(NotePitchClass::C, 4))
+        );
+        assert_eq!(found[1], None);
+        assert_eq!(found[2], None);
+        assert_eq!(
+            found[3].map(|nearest| nearest.note_pitch()),
+            Some(NotePitch::new(NotePitchClass::B, 5))
+        );
+    }
 }
@@ -0,0 +1,466 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    composition::Timeline,
+    dynamics::Dynamic,
+    note::Note,
+    pitch::NotePitch,
+    rhythm::{self, Duration, NoteValue, Tuplet},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A note as played, timed in seconds from the start of the performance.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PerformedNote {
+    note_pitch: NotePitch,
+    onset: f64,
+    length: f64,
+    velocity: u8,
+}
+
+impl PerformedNote {
+    /// A note played with a velocity of 64. Returns `None` if `onset` or `length` is negative or
+    /// not finite.
+    #[must_use]
+    pub fn new(note_pitch: NotePitch, onset: f64, length: f64) -> Option<Self> {
+        if !onset.is_finite() || !length.is_finite() || onset < 0.0 || length < 0.0 {
+            return None;
+        }
+        Some(Self {
+            note_pitch,
+            onset,
+            length,
+            velocity: 64,
+        })
+    }
+
+    /// The MIDI velocity the note was played with.
+    #[must_use]
+    pub fn with_velocity(self, velocity: u8) -> Self {
+        Self { velocity, ..self }
+    }
+
+    #[must_use]
+    pub fn note_pitch(&self) -> &NotePitch {
+        &self.note_pitch
+    }
+
+    #[must_use]
+    pub fn onset(&self) -> f64 {
+        self.onset
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    #[must_use]
+    pub fn end(&self) -> f64 {
+        self.onset + self.length
+    }
+
+    #[must_use]
+    pub fn velocity(&self) -> u8 {
+        self.velocity
+    }
+}
+
+/// The points that performed timings are snapped to, counted from the start of the performance.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Grid {
+    /// Every note of the value, such as every sixteenth.
+    Straight(NoteValue),
+    /// Every triplet note of the value, such as every triplet eighth.
+    Triplet(NoteValue),
+    /// Whichever of the straight and triplet grids fits the onsets of each beat best.
+    Detect(NoteValue),
+}
+
+impl Grid {
+    fn straight_step(value: NoteValue) -> Duration {
+        value.duration()
+    }
+
+    fn triplet_step(value: NoteValue) -> Duration {
+        Duration::tuplet(value, Tuplet::triplet())
+    }
+}
+
+/// Snaps [`PerformedNote`]s to a grid of score durations.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quantizer {
+    rhythm: rhythm::Rhythm,
+    grid: Grid,
+    strength: f64,
+    swing: f64,
+    minimum_length: Option<Duration>,
+    resolution: Duration,
+}
+
+impl Quantizer {
+    /// Snaps fully to `grid`, without swing, in beats and at the tempo of `rhythm`.
+    #[must_use]
+    pub fn new(rhythm: rhythm::Rhythm, grid: Grid) -> Self {
+        Self {
+            rhythm,
+            grid,
+            strength: 1.0,
+            swing: 0.5,
+            minimum_length: None,
+            resolution: Duration::new(1, 384).unwrap_or_else(|_| unreachable!("1/384 is not zero")),
+        }
+    }
+
+    /// How far each timing moves towards its grid point, from 0 (not at all) to 1 (all the
+    /// way). Timings that end up between grid points are rounded to the resolution.
+    #[must_use]
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = if strength.is_nan() {
+            1.0
+        } else {
+            strength.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// The share of each pair of straight grid steps taken by the first: 0.5 is straight and
+    /// 2/3 is a triplet swing. Swung timings are written as straight notes.
+    #[must_use]
+    pub fn with_swing(mut self, swing: f64) -> Self {
+        self.swing = if swing.is_nan() {
+            0.5
+        } else {
+            swing.clamp(0.5, 0.95)
+        };
+        self
+    }
+
+    /// Lengthens shorter notes to `minimum_length`. By default notes last at least one grid
+    /// step.
+    #[must_use]
+    pub fn with_minimum_length(mut self, minimum_length: Duration) -> Self {
+        self.minimum_length = Some(minimum_length);
+        self
+    }
+
+    /// The finest duration that partially snapped timings are rounded to; 1/384 by default. A
+    /// zero resolution is ignored.
+    #[must_use]
+    pub fn with_resolution(mut self, resolution: Duration) -> Self {
+        if !resolution.is_zero() {
+            self.resolution = resolution;
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn rhythm(&self) -> &rhythm::Rhythm {
+        &self.rhythm
+    }
+
+    #[must_use]
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    #[must_use]
+    pub fn strength(&self) -> f64 {
+        self.strength
+    }
+
+    #[must_use]
+    pub fn swing(&self) -> f64 {
+        self.swing
+    }
+
+    fn beat(&self) -> Duration {
+        *self.rhythm.beat_assignment().beat_duration()
+    }
+
+    /// Whole notes from the start after `seconds`.
+    fn position(&self, seconds: f64) -> f64 {
+        seconds / self.rhythm.tempo().one_beat_seconds() * self.beat().ratio().to_f64()
+    }
+
+    fn beat_index(&self, position: f64) -> u32 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let index = (position / self.beat().ratio().to_f64())
+            .floor()
+            .clamp(0.0, f64::from(u32::MAX)) as u32;
+        index
+    }
+
+    /// Undoes swing on `position`, in whole notes, for a grid of `step`.
+    fn unswing(&self, position: f64, step: f64) -> f64 {
+        let pair = 2.0 * step;
+        let pair_start = (position / pair).floor() * pair;
+        let into = position - pair_start;
+        let split = pair * self.swing;
+        let straight = if into < split {
+            into / (2.0 * self.swing)
+        } else {
+            step + (into - split) / (2.0 * (1.0 - self.swing))
+        };
+        pair_start + straight
+    }
+
+    /// The distance in whole notes from `position`, in straight time, to the nearest point of the
+    /// grid of `step`.
+    fn grid_error(&self, position: f64, step: Duration, swung: bool) -> f64 {
+        let step = step.ratio().to_f64();
+        let position = if swung {
+            self.unswing(position, step)
+        } else {
+            position
+        };
+        (position - (position / step).round() * step).abs()
+    }
+
+    /// The grid step to use in each beat that an onset falls in, and the step to use in any
+    /// other beat.
+    fn grids(&self, onsets: &[f64]) -> (BTreeMap<u32, Duration>, Duration) {
+        let mut grids = BTreeMap::new();
+        let value = match self.grid {
+            Grid::Straight(value) => return (grids, Grid::straight_step(value)),
+            Grid::Triplet(value) => return (grids, Grid::triplet_step(value)),
+            Grid::Detect(value) => value,
+        };
+        let straight = Grid::straight_step(value);
+        let triplet = Grid::triplet_step(value);
+
+        let mut errors: BTreeMap<u32, (f64, f64)> = BTreeMap::new();
+        for onset in onsets {
+            let error = errors.entry(self.beat_index(*onset)).or_default();
+            error.0 += self.grid_error(*onset, straight, true).powi(2);
+            error.1 += self.grid_error(*onset, triplet, false).powi(2);
+        }
+        for (beat, (straight_error, triplet_error)) in errors {
+            let step = if triplet_error < straight_error {
+                triplet
+            } else {
+                straight
+            };
+            grids.insert(beat, step);
+        }
+        (grids, straight)
+    }
+
+    /// Snaps `position` to the grid of `step`, returning an exact duration when snapping fully.
+    fn snap(&self, position: f64, step: Duration, swung: bool) -> Duration {
+        let step_f64 = step.ratio().to_f64();
+        let position = if swung {
+            self.unswing(position, step_f64)
+        } else {
+            position
+        };
+        let steps = (position / step_f64).round();
+        if self.strength >= 1.0 {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let steps = steps.clamp(0.0, f64::from(u32::MAX)) as u32;
+            return step * steps;
+        }
+        let partial = position + self.strength * (steps * step_f64 - position);
+        let resolution = self.resolution.ratio().to_f64();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let resolutions = (partial / resolution)
+            .round()
+            .clamp(0.0, f64::from(u32::MAX)) as u32;
+        self.resolution * resolutions
+    }
+
+    /// Quantizes `notes` into a timeline starting at the start of the performance. Each note
+    /// keeps the velocity it was played with as its dynamic.
+    #[must_use]
+    pub fn quantize(&self, notes: &[PerformedNote]) -> Timeline<Note> {
+        let onsets: Vec<f64> = notes.iter().map(|note| self.position(note.onset)).collect();
+        let (grids, default_step) = self.grids(&onsets);
+        let step_at = |position: f64| {
+            let step = grids
+                .get(&self.beat_index(position))
+                .copied()
+                .unwrap_or(default_step);
+            let swung = !matches!(self.grid, Grid::Triplet(_)) && step == default_step;
+            (step, swung)
+        };
+
+        let mut timeline = Timeline::new();
+        for (note, onset) in notes.iter().zip(onsets) {
+            let (step, swung) = step_at(onset);
+            let start = self.snap(onset, step, swung);
+            let end_position = self.position(note.end());
+            let (end_step, end_swung) = step_at(end_position);
+            let end = self.snap(end_position, end_step, end_swung);
+            let minimum_length = self.minimum_length.unwrap_or(step);
+            let length = end
+                .checked_sub(start)
+                .unwrap_or_default()
+                .max(minimum_length);
+            timeline.insert(
+                start,
+                Note::new(note.note_pitch, length).with_dynamic(Dynamic::Velocity(note.velocity)),
+            );
+        }
+        timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::NotePitchClass;
+    use crate::rhythm::{BeatAssignment, Tempo};
+
+    use super::*;
+
+    /// Quarter note beats at 60 bpm, so each second is a quarter note.
+    fn one_beat_per_second() -> rhythm::Rhythm {
+        rhythm::Rhythm::new(
+            Tempo::new(60.0).unwrap(),
+            BeatAssignment::new(Duration::new(1, 4).unwrap()),
+        )
+    }
+
+    fn performed(timings: &[(f64, f64)]) -> Vec<PerformedNote> {
+        timings
+            .iter()
+            .map(|(onset, length)| {
+                PerformedNote::new(NotePitch::new(NotePitchClass::C, 4), *onset, *length).unwrap()
+            })
+            .collect()
+    }
+
+    fn onsets_and_lengths(timeline: &Timeline<Note>) -> Vec<(Duration, Duration)> {
+        timeline
+            .iter()
+            .map(|(offset, note)| (*offset, *note.duration()))
+            .collect()
+    }
+
+    #[test]
+    fn snapping_to_straight_and_triplet_grids() {
+        let notes = performed(&[(0.02, 0.45), (0.48, 0.3), (1.27, 0.01), (1.9, 1.15)]);
+        let sixteenths =
+            Quantizer::new(one_beat_per_second(), Grid::Straight(NoteValue::Sixteenth));
+        assert_eq!(
+            onsets_and_lengths(&sixteenths.quantize(&notes)),
+            vec![
+                (Duration::zero(), Duration::new(1, 8).unwrap()),
+                (Duration::new(1, 8).unwrap(), Duration::new(1, 16).unwrap()),
+                (Duration::new(5, 16).unwrap(), Duration::new(1, 16).unwrap()),
+                (Duration::new(1, 2).unwrap(), Duration::new(1, 4).unwrap())
+            ]
+        );
+
+        let minimum = sixteenths
+            .clone()
+            .with_minimum_length(Duration::new(1, 8).unwrap());
+        assert_eq!(
+            onsets_and_lengths(&minimum.quantize(&notes))[2],
+            (Duration::new(5, 16).unwrap(), Duration::new(1, 8).unwrap())
+        );
+
+        let triplets = Quantizer::new(one_beat_per_second(), Grid::Triplet(NoteValue::Eighth));
+        assert_eq!(
+            onsets_and_lengths(&triplets.quantize(&performed(&[(0.35, 0.3)]))),
+            vec![(Duration::new(1, 12).unwrap(), Duration::new(1, 12).unwrap())]
+        );
+        let loud = performed(&[(0.0, 1.0)])[0].with_velocity(100);
+        let quantized = triplets.quantize(&[loud]);
+        assert_eq!(
+            quantized.events_at(&Duration::zero())[0].dynamic(),
+            Some(Dynamic::Velocity(100))
+        );
+
+        // halfway to the grid, to the nearest 1/384
+        let half = sixteenths.with_strength(0.5);
+        assert_eq!(
+            half.quantize(&performed(&[(0.125, 0.125)]))
+                .offsets()
+                .collect::<Vec<_>>(),
+            vec![&Duration::new(3, 64).unwrap()]
+        );
+        assert!(PerformedNote::new(NotePitch::new(NotePitchClass::C, 4), -1.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn swing_and_grid_detection() {
+        // eighths swung two to one are written straight
+        let swung = Quantizer::new(one_beat_per_second(), Grid::Straight(NoteValue::Eighth))
+            .with_swing(2.0 / 3.0);
+        assert_eq!(
+            onsets_and_lengths(&swung.quantize(&performed(&[
+                (0.0, 0.66),
+                (0.68, 0.3),
+                (1.0, 0.64),
+                (1.66, 0.33)
+            ]))),
+            vec![
+                (Duration::zero(), Duration::new(1, 8).unwrap()),
+                (Duration::new(1, 8).unwrap(), Duration::new(1, 8).unwrap()),
+                (Duration::new(1, 4).unwrap(), Duration::new(1, 8).unwrap()),
+                (Duration::new(3, 8).unwrap(), Duration::new(1, 8).unwrap())
+            ]
+        );
+
+        // a beat of sixteenths then a beat of triplet eighths
+        let detect = Quantizer::new(one_beat_per_second(), Grid::Detect(NoteValue::Sixteenth));
+        let timeline = detect.quantize(&performed(&[
+            (0.01, 0.2),
+            (0.26, 0.2),
+            (0.49, 0.2),
+            (0.76, 0.2),
+            (1.0, 0.3),
+            (1.34, 0.3),
+            (1.65, 0.3),
+        ]));
+        assert_eq!(
+            timeline.offsets().copied().collect::<Vec<_>>(),
+            vec![
+                Duration::zero(),
+                Duration::new(1, 16).unwrap(),
+                Duration::new(1, 8).unwrap(),
+                Duration::new(3, 16).unwrap(),
+                Duration::new(1, 4).unwrap(),
+                Duration::new(1, 3).unwrap(),
+                Duration::new(5, 12).unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn grids_coarser_than_the_beat() {
+        // quarter note triplets span two beats
+        let triplets = Quantizer::new(one_beat_per_second(), Grid::Triplet(NoteValue::Quarter));
+        assert_eq!(
+            triplets
+                .quantize(&performed(&[
+                    (0.0, 0.6),
+                    (2.0 / 3.0, 0.6),
+                    (4.0 / 3.0, 0.6)
+                ]))
+                .offsets()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![
+                Duration::zero(),
+                Duration::new(1, 6).unwrap(),
+                Duration::new(1, 3).unwrap()
+            ]
+        );
+
+        let halves = Quantizer::new(one_beat_per_second(), Grid::Straight(NoteValue::Half));
+        assert_eq!(
+            halves
+                .quantize(&performed(&[(0.9, 0.5), (1.2, 0.5), (2.9, 0.5)]))
+                .offsets()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Duration::zero(), Duration::new(1, 2).unwrap()]
+        );
+    }
+}
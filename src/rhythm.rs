@@ -1,7 +1,18 @@
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
+};
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+/// A non-negative rational number kept in lowest terms.
+///
+/// All arithmetic is exact. The plain operators panic on overflow (like the primitive integer
+/// operators do in debug builds) while the `checked_*` methods return `None` instead.
+#[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Ratio {
     numerator: u32,
@@ -15,17 +26,38 @@ pub enum NewRatioError {
 }
 
 impl Ratio {
+    /// Creates a ratio reduced to lowest terms.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NewRatioError::NumeratorOrDenominatorZero`] if either argument is zero. Use
+    /// [`Ratio::zero`] to obtain a zero ratio.
     pub fn new(numerator: u32, denominator: u32) -> Result<Self, NewRatioError> {
         if numerator == 0 || denominator == 0 {
             return Err(NewRatioError::NumeratorOrDenominatorZero);
         }
 
+        let divisor = gcd(u128::from(numerator), u128::from(denominator));
+        #[allow(clippy::cast_possible_truncation)]
         Ok(Self {
-            numerator,
-            denominator,
+            numerator: (u128::from(numerator) / divisor) as u32,
+            denominator: (u128::from(denominator) / divisor) as u32,
         })
     }
 
+    #[must_use]
+    pub fn zero() -> Self {
+        Self {
+            numerator: 0,
+            denominator: 1,
+        }
+    }
+
+    #[must_use]
+    pub fn one() -> Self {
+        Self::from(1)
+    }
+
     #[must_use]
     pub fn numerator(&self) -> u32 {
         self.numerator
@@ -36,10 +68,105 @@ impl Ratio {
         self.denominator
     }
 
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
     #[must_use]
     pub fn to_f64(&self) -> f64 {
         f64::from(self.numerator()) / f64::from(self.denominator())
     }
+
+    /// The largest integer not greater than the ratio.
+    #[must_use]
+    pub fn floor(&self) -> u32 {
+        self.numerator / self.denominator
+    }
+
+    /// The nearest integer to the ratio, rounding halves up.
+    #[must_use]
+    pub fn round(&self) -> u32 {
+        let rounded = (2 * u64::from(self.numerator) + u64::from(self.denominator))
+            / (2 * u64::from(self.denominator));
+        // the result is at most numerator / denominator + 1/2, so it only overflows for u32::MAX/1
+        u32::try_from(rounded).unwrap_or(u32::MAX)
+    }
+
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let numerator = u128::from(self.numerator) * u128::from(rhs.denominator)
+            + u128::from(rhs.numerator) * u128::from(self.denominator);
+        let denominator = u128::from(self.denominator) * u128::from(rhs.denominator);
+        Self::from_u128(numerator, denominator)
+    }
+
+    /// Subtracts `rhs`, returning `None` if the result would be negative or overflow.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let lhs_numerator = u128::from(self.numerator) * u128::from(rhs.denominator);
+        let rhs_numerator = u128::from(rhs.numerator) * u128::from(self.denominator);
+        let numerator = lhs_numerator.checked_sub(rhs_numerator)?;
+        let denominator = u128::from(self.denominator) * u128::from(rhs.denominator);
+        Self::from_u128(numerator, denominator)
+    }
+
+    #[must_use]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let numerator = u128::from(self.numerator) * u128::from(rhs.numerator);
+        let denominator = u128::from(self.denominator) * u128::from(rhs.denominator);
+        Self::from_u128(numerator, denominator)
+    }
+
+    /// Divides by `rhs`, returning `None` if `rhs` is zero or the result would overflow.
+    #[must_use]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let numerator = u128::from(self.numerator) * u128::from(rhs.denominator);
+        let denominator = u128::from(self.denominator) * u128::from(rhs.numerator);
+        Self::from_u128(numerator, denominator)
+    }
+
+    fn from_u128(numerator: u128, denominator: u128) -> Option<Self> {
+        if numerator == 0 {
+            return Some(Self::zero());
+        }
+        let divisor = gcd(numerator, denominator);
+        Some(Self {
+            numerator: u32::try_from(numerator / divisor).ok()?,
+            denominator: u32::try_from(denominator / divisor).ok()?,
+        })
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl From<u32> for Ratio {
+    fn from(value: u32) -> Self {
+        Self {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+}
+
+impl Default for Ratio {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
 }
 
 impl PartialEq for Ratio {
@@ -58,35 +185,298 @@ impl PartialOrd for Ratio {
 
 impl Ord for Ratio {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // numerator and denominator are non-zero
-        let other_ratio = other.to_f64();
-        let self_ratio = self.to_f64();
-
-        if self_ratio < other_ratio {
-            std::cmp::Ordering::Less
-        } else if self_ratio > other_ratio {
-            std::cmp::Ordering::Greater
-        } else {
-            std::cmp::Ordering::Equal
+        // denominators are non-zero, so cross-multiplying preserves the order
+        let self_cross = u64::from(self.numerator) * u64::from(other.denominator);
+        let other_cross = u64::from(other.numerator) * u64::from(self.denominator);
+        self_cross.cmp(&other_cross)
+    }
+}
+
+impl Hash for Ratio {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // equal ratios must hash equally even if one was deserialized without being reduced
+        let divisor = gcd(u128::from(self.numerator), u128::from(self.denominator)).max(1);
+        (u128::from(self.numerator) / divisor).hash(state);
+        (u128::from(self.denominator) / divisor).hash(state);
+    }
+}
+
+impl Add for Ratio {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("overflow when adding ratios")
+    }
+}
+
+impl Sub for Ratio {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("negative result or overflow when subtracting ratios")
+    }
+}
+
+impl Mul for Ratio {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.checked_mul(rhs)
+            .expect("overflow when multiplying ratios")
+    }
+}
+
+impl Mul<u32> for Ratio {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self::Output {
+        self * Self::from(rhs)
+    }
+}
+
+impl Div for Ratio {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.checked_div(rhs)
+            .expect("division by zero or overflow when dividing ratios")
+    }
+}
+
+impl Div<u32> for Ratio {
+    type Output = Self;
+
+    fn div(self, rhs: u32) -> Self::Output {
+        self / Self::from(rhs)
+    }
+}
+
+impl AddAssign for Ratio {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Ratio {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for Ratio {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Ratio> for Ratio {
+    fn sum<I: Iterator<Item = &'a Ratio>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewTimeSignatureError {
+    NoGroups,
+    ZeroNumerator,
+    ZeroDenominator,
+    TooLong,
+}
+
+impl Display for NewTimeSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoGroups => f.write_str("an additive time signature needs at least one group"),
+            Self::ZeroNumerator => f.write_str("a time signature's numerator cannot be zero"),
+            Self::ZeroDenominator => f.write_str("a time signature's denominator cannot be zero"),
+            Self::TooLong => f.write_str("the groups of a time signature add up to too many beats"),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+impl std::error::Error for NewTimeSignatureError {}
+
+/// How the beats of a bar divide.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TimeSignatureKind {
+    /// Beats of equal length that divide in two, such as 2/4, 3/4 and 4/4.
+    Simple,
+    /// Beats of equal, dotted length that divide in three, such as 6/8, 9/8 and 12/8.
+    Compound,
+    /// Beats of unequal length, such as 5/8, 7/8 and 2+2+3/8.
+    Irregular,
+}
+
+/// A time signature as written, so 6/8 and 3/4 stay distinct, as do 7/8 and 2+2+3/8.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TimeSignature {
-    ratio: Ratio,
+    /// The numerator, split into the groups of an additive time signature.
+    groups: Vec<u32>,
+    denominator: u32,
 }
 
 impl TimeSignature {
+    /// # Errors
+    ///
+    /// Returns an error if `numerator` or `denominator` is zero.
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self, NewTimeSignatureError> {
+        Self::additive(vec![numerator], denominator)
+    }
+
+    /// An additive time signature such as 2+2+3/8, whose numerator is the sum of `groups`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no groups, a group or `denominator` is zero, or the groups
+    /// add up to more than `u32::MAX`.
+    pub fn additive(groups: Vec<u32>, denominator: u32) -> Result<Self, NewTimeSignatureError> {
+        if groups.is_empty() {
+            return Err(NewTimeSignatureError::NoGroups);
+        }
+        if groups.contains(&0) {
+            return Err(NewTimeSignatureError::ZeroNumerator);
+        }
+        if denominator == 0 {
+            return Err(NewTimeSignatureError::ZeroDenominator);
+        }
+        groups
+            .iter()
+            .try_fold(0u32, |sum, group| sum.checked_add(*group))
+            .ok_or(NewTimeSignatureError::TooLong)?;
+        Ok(Self {
+            groups,
+            denominator,
+        })
+    }
+
     #[must_use]
-    pub fn new(ratio: Ratio) -> Self {
-        Self { ratio }
+    pub fn numerator(&self) -> u32 {
+        self.groups.iter().sum()
     }
 
     #[must_use]
-    pub fn ratio(&self) -> &Ratio {
-        &self.ratio
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    /// The numerator as written: one group unless the time signature is additive.
+    #[must_use]
+    pub fn groups(&self) -> &[u32] {
+        &self.groups
+    }
+
+    #[must_use]
+    pub fn is_additive(&self) -> bool {
+        self.groups.len() > 1
+    }
+
+    /// The length of a bar in whole notes, in lowest terms.
+    #[must_use]
+    pub fn ratio(&self) -> Ratio {
+        Ratio::new(self.numerator(), self.denominator)
+            .unwrap_or_else(|_| unreachable!("time signatures have non-zero terms"))
+    }
+
+    #[must_use]
+    pub fn bar_duration(&self) -> Duration {
+        Duration::new_from_ratio(self.ratio())
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> TimeSignatureKind {
+        if self.is_additive() {
+            return match self.groups[0] {
+                size if self.groups.iter().any(|group| *group != size) => {
+                    TimeSignatureKind::Irregular
+                }
+                1 | 2 => TimeSignatureKind::Simple,
+                3 => TimeSignatureKind::Compound,
+                _ => TimeSignatureKind::Irregular,
+            };
+        }
+        match self.numerator() {
+            1..=4 => TimeSignatureKind::Simple,
+            numerator if numerator % 3 == 0 => TimeSignatureKind::Compound,
+            _ => TimeSignatureKind::Irregular,
+        }
+    }
+
+    /// The number of `1/denominator` notes in each beat of a bar.
+    ///
+    /// Additive time signatures beat in their groups. Otherwise simple time signatures beat in
+    /// single notes and compound ones in threes. Irregular ones with a denominator of eight or
+    /// more beat in twos with a three at the end, so 7/8 beats as 2+2+3, while slower ones beat
+    /// in single notes.
+    #[must_use]
+    pub fn beat_groups(&self) -> Vec<u32> {
+        if self.is_additive() {
+            return self.groups.clone();
+        }
+        let numerator = self.numerator();
+        match self.kind() {
+            TimeSignatureKind::Compound => vec![3; (numerator / 3) as usize],
+            TimeSignatureKind::Irregular if self.denominator >= 8 => {
+                let mut groups = vec![2; (numerator / 2) as usize];
+                if numerator % 2 == 1 {
+                    if let Some(last) = groups.last_mut() {
+                        *last = 3;
+                    }
+                }
+                groups
+            }
+            _ => vec![1; numerator as usize],
+        }
+    }
+
+    #[must_use]
+    pub fn beat_durations(&self) -> Vec<Duration> {
+        self.beat_groups()
+            .into_iter()
+            .map(|group| {
+                Duration::new(group, self.denominator)
+                    .unwrap_or_else(|_| unreachable!("beat groups are never empty"))
+            })
+            .collect()
+    }
+
+    /// The beat to count in: a beat group when they are all the same length, such as the dotted
+    /// quarter of 6/8, and otherwise the `1/denominator` note.
+    #[must_use]
+    pub fn default_beat_assignment(&self) -> BeatAssignment {
+        let groups = self.beat_groups();
+        let group = match groups.first() {
+            Some(first) if groups.iter().all(|group| group == first) => *first,
+            _ => 1,
+        };
+        BeatAssignment::new(
+            Duration::new(group, self.denominator)
+                .unwrap_or_else(|_| unreachable!("time signatures have non-zero terms")),
+        )
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            groups: vec![4],
+            denominator: 4,
+        }
+    }
+}
+
+impl Display for TimeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, group) in self.groups.iter().enumerate() {
+            if index > 0 {
+                f.write_str("+")?;
+            }
+            write!(f, "{group}")?;
+        }
+        write!(f, "/{}", self.denominator)
     }
 }
 
@@ -153,13 +543,23 @@ impl BeatAssignment {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+/// Implemented by anything that occupies a span of musical time.
+pub trait HasDuration {
+    fn duration(&self) -> Duration;
+}
+
+/// A length of musical time measured in whole notes; a quarter note is `1/4`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Duration {
     ratio: Ratio,
 }
 
 impl Duration {
+    /// # Errors
+    ///
+    /// Returns [`NewRatioError::NumeratorOrDenominatorZero`] if either argument is zero. Use
+    /// [`Duration::zero`] to obtain a zero duration.
     pub fn new(numerator: u32, denominator: u32) -> Result<Self, NewRatioError> {
         Ok(Self {
             ratio: Ratio::new(numerator, denominator)?,
@@ -173,6 +573,13 @@ impl Duration {
         }
     }
 
+    #[must_use]
+    pub fn zero() -> Self {
+        Self {
+            ratio: Ratio::zero(),
+        }
+    }
+
     #[must_use]
     pub fn numerator(&self) -> u32 {
         self.ratio.numerator()
@@ -187,6 +594,417 @@ impl Duration {
     pub fn ratio(&self) -> &Ratio {
         &self.ratio
     }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.ratio.is_zero()
+    }
+
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.ratio.checked_add(rhs.ratio).map(Self::new_from_ratio)
+    }
+
+    /// Subtracts `rhs`, returning `None` if `rhs` is longer than `self` or on overflow.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.ratio.checked_sub(rhs.ratio).map(Self::new_from_ratio)
+    }
+
+    #[must_use]
+    pub fn checked_mul(self, rhs: Ratio) -> Option<Self> {
+        self.ratio.checked_mul(rhs).map(Self::new_from_ratio)
+    }
+
+    #[must_use]
+    pub fn checked_div(self, rhs: Ratio) -> Option<Self> {
+        self.ratio.checked_div(rhs).map(Self::new_from_ratio)
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.ratio.fmt(f)
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new_from_ratio(self.ratio + rhs.ratio)
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new_from_ratio(self.ratio - rhs.ratio)
+    }
+}
+
+impl Mul<Ratio> for Duration {
+    type Output = Self;
+
+    fn mul(self, rhs: Ratio) -> Self::Output {
+        Self::new_from_ratio(self.ratio * rhs)
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self::Output {
+        Self::new_from_ratio(self.ratio * rhs)
+    }
+}
+
+impl Div<Ratio> for Duration {
+    type Output = Self;
+
+    fn div(self, rhs: Ratio) -> Self::Output {
+        Self::new_from_ratio(self.ratio / rhs)
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Self;
+
+    fn div(self, rhs: u32) -> Self::Output {
+        Self::new_from_ratio(self.ratio / rhs)
+    }
+}
+
+/// Dividing two durations gives how many times the divisor fits into the dividend.
+impl Div for Duration {
+    type Output = Ratio;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.ratio / rhs.ratio
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for Duration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Duration> for Duration {
+    fn sum<I: Iterator<Item = &'a Duration>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// The undotted note values from a whole note down to a 256th note.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+    SixtyFourth,
+    HundredTwentyEighth,
+    TwoHundredFiftySixth,
+}
+
+impl NoteValue {
+    /// Every note value, longest first.
+    pub const ALL: [NoteValue; 9] = [
+        NoteValue::Whole,
+        NoteValue::Half,
+        NoteValue::Quarter,
+        NoteValue::Eighth,
+        NoteValue::Sixteenth,
+        NoteValue::ThirtySecond,
+        NoteValue::SixtyFourth,
+        NoteValue::HundredTwentyEighth,
+        NoteValue::TwoHundredFiftySixth,
+    ];
+
+    /// How many times a whole note is halved to give this value.
+    #[must_use]
+    pub fn halvings(self) -> u32 {
+        self as u32
+    }
+
+    #[must_use]
+    pub fn duration(self) -> Duration {
+        Duration {
+            ratio: Ratio {
+                numerator: 1,
+                denominator: 1 << self.halvings(),
+            },
+        }
+    }
+
+    /// The note value lasting exactly `duration`, if there is one.
+    #[must_use]
+    pub fn from_duration(duration: Duration) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|value| value.duration() == duration)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewTupletError {
+    ZeroNotes,
+}
+
+impl Display for NewTupletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a tuplet must have at least one note in the time of at least one note")
+    }
+}
+
+impl std::error::Error for NewTupletError {}
+
+/// `actual` notes played in the time of `normal` notes of the same value, such as the 3:2 of a
+/// triplet.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tuplet {
+    actual: u32,
+    normal: u32,
+}
+
+impl Tuplet {
+    /// # Errors
+    ///
+    /// Returns [`NewTupletError::ZeroNotes`] if either argument is zero.
+    pub fn new(actual: u32, normal: u32) -> Result<Self, NewTupletError> {
+        if actual == 0 || normal == 0 {
+            return Err(NewTupletError::ZeroNotes);
+        }
+        Ok(Self { actual, normal })
+    }
+
+    /// `actual` notes in the time of the largest power of two below `actual`, as a tuplet is
+    /// usually read when only its first number is written: 3:2, 5:4, 6:4, 7:4, 9:8 and so on.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NewTupletError::ZeroNotes`] if `actual` is less than two.
+    pub fn conventional(actual: u32) -> Result<Self, NewTupletError> {
+        if actual < 2 {
+            return Err(NewTupletError::ZeroNotes);
+        }
+        Self::new(actual, 1 << (actual - 1).ilog2())
+    }
+
+    #[must_use]
+    pub fn triplet() -> Self {
+        Self {
+            actual: 3,
+            normal: 2,
+        }
+    }
+
+    #[must_use]
+    pub fn actual(&self) -> u32 {
+        self.actual
+    }
+
+    #[must_use]
+    pub fn normal(&self) -> u32 {
+        self.normal
+    }
+
+    /// The factor a note value is scaled by inside the tuplet.
+    #[must_use]
+    pub fn ratio(&self) -> Ratio {
+        Ratio::new(self.normal, self.actual)
+            .unwrap_or_else(|_| unreachable!("both terms of a tuplet are positive"))
+    }
+}
+
+impl Display for Tuplet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.actual, self.normal)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewNotatedDurationError {
+    TooManyDots,
+}
+
+impl Display for NewNotatedDurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a note can have at most {MAX_DOTS} dots")
+    }
+}
+
+impl std::error::Error for NewNotatedDurationError {}
+
+/// The most dots [`NotatedDuration`] accepts.
+pub const MAX_DOTS: u8 = 4;
+
+/// The most dots [`Duration::decompose`] writes, to keep the result readable.
+const DECOMPOSE_MAX_DOTS: u8 = 2;
+
+/// A duration as it is written: a note value with optional dots, optionally inside a tuplet.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NotatedDuration {
+    value: NoteValue,
+    dots: u8,
+    tuplet: Option<Tuplet>,
+}
+
+impl NotatedDuration {
+    /// # Errors
+    ///
+    /// Returns [`NewNotatedDurationError::TooManyDots`] if `dots` is more than [`MAX_DOTS`].
+    pub fn new(
+        value: NoteValue,
+        dots: u8,
+        tuplet: Option<Tuplet>,
+    ) -> Result<Self, NewNotatedDurationError> {
+        if dots > MAX_DOTS {
+            return Err(NewNotatedDurationError::TooManyDots);
+        }
+        Ok(Self {
+            value,
+            dots,
+            tuplet,
+        })
+    }
+
+    #[must_use]
+    pub fn value(&self) -> NoteValue {
+        self.value
+    }
+
+    #[must_use]
+    pub fn dots(&self) -> u8 {
+        self.dots
+    }
+
+    #[must_use]
+    pub fn tuplet(&self) -> Option<Tuplet> {
+        self.tuplet
+    }
+
+    /// The single notated duration, with at most two dots, that lasts exactly `duration`.
+    #[must_use]
+    pub fn from_duration(duration: Duration) -> Option<Self> {
+        match duration.decompose()?.as_slice() {
+            [notated] => Some(*notated),
+            _ => None,
+        }
+    }
+}
+
+impl HasDuration for NotatedDuration {
+    fn duration(&self) -> Duration {
+        // each dot adds half of the previous one: 2 - 1/2^dots times the value
+        let dotted = Ratio::new((2 << self.dots) - 1, 1 << self.dots)
+            .unwrap_or_else(|_| unreachable!("both terms are positive"));
+        let duration = self.value.duration() * dotted;
+        self.tuplet
+            .map_or(duration, |tuplet| duration * tuplet.ratio())
+    }
+}
+
+impl From<NoteValue> for NotatedDuration {
+    fn from(value: NoteValue) -> Self {
+        Self {
+            value,
+            dots: 0,
+            tuplet: None,
+        }
+    }
+}
+
+impl Duration {
+    /// # Errors
+    ///
+    /// Returns [`NewNotatedDurationError::TooManyDots`] if `dots` is more than [`MAX_DOTS`].
+    pub fn dotted(value: NoteValue, dots: u8) -> Result<Self, NewNotatedDurationError> {
+        Ok(NotatedDuration::new(value, dots, None)?.duration())
+    }
+
+    /// The duration of one note of `value` inside `tuplet`, such as an eighth-note triplet.
+    #[must_use]
+    pub fn tuplet(value: NoteValue, tuplet: Tuplet) -> Self {
+        value.duration() * tuplet.ratio()
+    }
+
+    /// Expresses the duration as notated durations to be tied together, longest first, using at
+    /// most two dots per note and no values longer than a whole note.
+    ///
+    /// A duration whose denominator is not a power of two is written entirely inside the
+    /// conventional tuplet for its odd factor, so that a third of a whole note is a half-note
+    /// triplet. Returns `None` if a value shorter than a 256th note would be needed, and an
+    /// empty list for a zero duration.
+    #[must_use]
+    pub fn decompose(&self) -> Option<Vec<NotatedDuration>> {
+        let odd_factor = self.denominator() >> self.denominator().trailing_zeros();
+        // an odd factor of one means no tuplet is needed
+        let tuplet = Tuplet::conventional(odd_factor).ok();
+        let written = tuplet.map_or(Some(*self), |tuplet| {
+            self.checked_mul(Ratio::new(tuplet.actual, tuplet.normal).ok()?)
+        })?;
+
+        let shortest = NoteValue::TwoHundredFiftySixth;
+        let units = written.checked_mul(Ratio::from(1 << shortest.halvings()))?;
+        if units.denominator() != 1 {
+            return None;
+        }
+        let whole_units = 1 << shortest.halvings();
+        let mut units = units.numerator();
+        let mut notated = Vec::new();
+        // whole notes past the first cannot join the rest with dots
+        while units >= 2 * whole_units {
+            notated.push(NotatedDuration {
+                value: NoteValue::Whole,
+                dots: 0,
+                tuplet,
+            });
+            units -= whole_units;
+        }
+        let mut values = NoteValue::ALL.into_iter().peekable();
+        while let Some(value) = values.next() {
+            let value_units = whole_units >> value.halvings();
+            if units & value_units == 0 {
+                continue;
+            }
+            let mut dots = 0;
+            while dots < DECOMPOSE_MAX_DOTS
+                && values
+                    .next_if(|next| units & (whole_units >> next.halvings()) != 0)
+                    .is_some()
+            {
+                dots += 1;
+            }
+            notated.push(NotatedDuration {
+                value,
+                dots,
+                tuplet,
+            });
+        }
+        Some(notated)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -250,7 +1068,7 @@ impl Metre {
 
     #[must_use]
     pub fn bars_from_duration(&self, duration: &Duration) -> f64 {
-        let bar_duration = Duration::new_from_ratio(self.time_signature().ratio().clone());
+        let bar_duration = self.time_signature().bar_duration();
         let seconds_per_bar = self.rhythm.seconds_from_duration(&bar_duration);
         self.rhythm.seconds_from_duration(duration) / seconds_per_bar
     }
@@ -319,4 +1137,237 @@ mod tests {
         assert!(duration3 > duration4);
         assert!(duration3 > duration5);
     }
+
+    #[test]
+    fn ratio_is_reduced() {
+        let ratio = Ratio::new(6, 8).unwrap();
+        assert_eq!(ratio.numerator(), 3);
+        assert_eq!(ratio.denominator(), 4);
+    }
+
+    #[test]
+    fn duration_arithmetic_is_exact() {
+        let dotted_eighth = Duration::new(3, 16).unwrap();
+        let triplet_quarter = Duration::new(1, 6).unwrap();
+
+        let sum = dotted_eighth + triplet_quarter;
+        assert_eq!(sum, Duration::new(17, 48).unwrap());
+        assert_eq!(sum.numerator(), 17);
+        assert_eq!(sum.denominator(), 48);
+
+        assert_eq!(sum - triplet_quarter, dotted_eighth);
+        assert_eq!(triplet_quarter * 3, Duration::new(1, 2).unwrap());
+        assert_eq!(dotted_eighth / 3, Duration::new(1, 16).unwrap());
+        assert_eq!(
+            dotted_eighth * Ratio::new(2, 3).unwrap(),
+            Duration::new(1, 8).unwrap()
+        );
+        assert_eq!(
+            Duration::new(1, 2).unwrap() / Duration::new(1, 8).unwrap(),
+            Ratio::from(4)
+        );
+        assert!((triplet_quarter - triplet_quarter).is_zero());
+    }
+
+    #[test]
+    fn ratio_floor_and_round() {
+        assert_eq!(Ratio::new(7, 2).unwrap().floor(), 3);
+        assert_eq!(Ratio::new(7, 2).unwrap().round(), 4);
+        assert_eq!(Ratio::new(10, 3).unwrap().round(), 3);
+        assert_eq!(Ratio::zero().round(), 0);
+    }
+
+    #[test]
+    fn duration_checked_arithmetic() {
+        let quarter = Duration::new(1, 4).unwrap();
+        let half = Duration::new(1, 2).unwrap();
+
+        assert_eq!(quarter.checked_sub(half), None);
+        assert_eq!(half.checked_sub(quarter), Some(quarter));
+        assert_eq!(quarter.checked_div(Ratio::zero()), None);
+
+        let huge = Duration::new(u32::MAX, 1).unwrap();
+        assert_eq!(huge.checked_add(huge), None);
+        assert_eq!(huge.checked_mul(Ratio::from(2)), None);
+    }
+
+    #[test]
+    fn duration_sum_and_hash() {
+        use std::collections::HashSet;
+
+        let durations = [
+            Duration::new(1, 8).unwrap(),
+            Duration::new(1, 12).unwrap(),
+            Duration::new(1, 24).unwrap(),
+        ];
+        assert_eq!(
+            durations.iter().sum::<Duration>(),
+            Duration::new(1, 4).unwrap()
+        );
+        assert_eq!(
+            Vec::<Duration>::new().into_iter().sum::<Duration>(),
+            Duration::zero()
+        );
+
+        let mut set = HashSet::new();
+        set.insert(Duration::new(2, 8).unwrap());
+        assert!(set.contains(&Duration::new(1, 4).unwrap()));
+    }
+
+    #[test]
+    fn note_values_dots_and_tuplets() {
+        assert_eq!(NoteValue::Quarter.duration(), Duration::new(1, 4).unwrap());
+        assert_eq!(
+            NoteValue::TwoHundredFiftySixth.duration(),
+            Duration::new(1, 256).unwrap()
+        );
+        assert_eq!(
+            Duration::dotted(NoteValue::Eighth, 1),
+            Ok(Duration::new(3, 16).unwrap())
+        );
+        assert_eq!(
+            Duration::dotted(NoteValue::Half, 2),
+            Ok(Duration::new(7, 8).unwrap())
+        );
+        assert_eq!(
+            Duration::dotted(NoteValue::Half, 5),
+            Err(NewNotatedDurationError::TooManyDots)
+        );
+        assert_eq!(
+            Duration::tuplet(NoteValue::Eighth, Tuplet::triplet()),
+            Duration::new(1, 12).unwrap()
+        );
+        assert_eq!(
+            Duration::tuplet(NoteValue::Sixteenth, Tuplet::conventional(5).unwrap()),
+            Duration::new(1, 20).unwrap()
+        );
+        assert_eq!(Tuplet::conventional(7).unwrap().normal(), 4);
+        assert_eq!(Tuplet::conventional(9).unwrap().normal(), 8);
+        assert_eq!(Tuplet::new(3, 0), Err(NewTupletError::ZeroNotes));
+
+        let dotted_eighth = NotatedDuration::from_duration(Duration::new(3, 16).unwrap()).unwrap();
+        assert_eq!(dotted_eighth.value(), NoteValue::Eighth);
+        assert_eq!(dotted_eighth.dots(), 1);
+        assert_eq!(dotted_eighth.tuplet(), None);
+
+        let triplet = NotatedDuration::from_duration(Duration::new(1, 12).unwrap()).unwrap();
+        assert_eq!(triplet.value(), NoteValue::Eighth);
+        assert_eq!(triplet.tuplet(), Some(Tuplet::triplet()));
+        assert_eq!(triplet.duration(), Duration::new(1, 12).unwrap());
+
+        assert_eq!(
+            NotatedDuration::from_duration(Duration::new(5, 16).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn decompose_into_tied_values() {
+        let decompose = |numerator, denominator| {
+            Duration::new(numerator, denominator)
+                .unwrap()
+                .decompose()
+                .unwrap()
+                .iter()
+                .map(|notated| (notated.value(), notated.dots()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            decompose(5, 16),
+            vec![(NoteValue::Quarter, 0), (NoteValue::Sixteenth, 0)]
+        );
+        assert_eq!(
+            decompose(15, 16),
+            vec![(NoteValue::Half, 2), (NoteValue::Sixteenth, 0)]
+        );
+        assert_eq!(
+            decompose(9, 4),
+            vec![
+                (NoteValue::Whole, 0),
+                (NoteValue::Whole, 0),
+                (NoteValue::Quarter, 0)
+            ]
+        );
+        assert_eq!(decompose(3, 2), vec![(NoteValue::Whole, 1)]);
+        assert_eq!(
+            decompose(5, 24),
+            vec![(NoteValue::Quarter, 0), (NoteValue::Sixteenth, 0)]
+        );
+        assert!(Duration::zero().decompose().unwrap().is_empty());
+        assert_eq!(Duration::new(1, 512).unwrap().decompose(), None);
+
+        for (numerator, denominator) in [(13, 32), (5, 24), (7, 3), (11, 20), (255, 256)] {
+            let duration = Duration::new(numerator, denominator).unwrap();
+            let total: Duration = duration
+                .decompose()
+                .unwrap()
+                .iter()
+                .map(HasDuration::duration)
+                .sum();
+            assert_eq!(total, duration);
+        }
+    }
+
+    #[test]
+    fn time_signatures() {
+        let six_eight = TimeSignature::new(6, 8).unwrap();
+        let three_four = TimeSignature::new(3, 4).unwrap();
+        assert_ne!(six_eight, three_four);
+        assert_eq!(six_eight.bar_duration(), three_four.bar_duration());
+        assert_eq!(six_eight.to_string(), "6/8");
+        assert_eq!(six_eight.kind(), TimeSignatureKind::Compound);
+        assert_eq!(three_four.kind(), TimeSignatureKind::Simple);
+        assert_eq!(six_eight.beat_groups(), vec![3, 3]);
+        assert_eq!(
+            six_eight.default_beat_assignment(),
+            BeatAssignment::new(Duration::new(3, 8).unwrap())
+        );
+        assert_eq!(
+            three_four.default_beat_assignment(),
+            BeatAssignment::new(Duration::new(1, 4).unwrap())
+        );
+
+        let seven_eight = TimeSignature::new(7, 8).unwrap();
+        let additive = TimeSignature::additive(vec![2, 2, 3], 8).unwrap();
+        assert_eq!(seven_eight.kind(), TimeSignatureKind::Irregular);
+        assert_eq!(additive.kind(), TimeSignatureKind::Irregular);
+        assert_eq!(seven_eight.beat_groups(), additive.beat_groups());
+        assert_ne!(seven_eight, additive);
+        assert_eq!(additive.to_string(), "2+2+3/8");
+        assert_eq!(additive.numerator(), 7);
+        assert_eq!(
+            additive.beat_durations(),
+            vec![
+                Duration::new(1, 4).unwrap(),
+                Duration::new(1, 4).unwrap(),
+                Duration::new(3, 8).unwrap()
+            ]
+        );
+        assert_eq!(
+            additive.default_beat_assignment(),
+            BeatAssignment::new(Duration::new(1, 8).unwrap())
+        );
+        assert_eq!(
+            TimeSignature::additive(vec![3, 3, 3], 8).unwrap().kind(),
+            TimeSignatureKind::Compound
+        );
+        assert_eq!(TimeSignature::new(5, 4).unwrap().beat_groups(), vec![1; 5]);
+
+        assert_eq!(
+            TimeSignature::additive(Vec::new(), 8),
+            Err(NewTimeSignatureError::NoGroups)
+        );
+        assert_eq!(
+            TimeSignature::new(0, 4),
+            Err(NewTimeSignatureError::ZeroNumerator)
+        );
+        assert_eq!(
+            TimeSignature::new(4, 0),
+            Err(NewTimeSignatureError::ZeroDenominator)
+        );
+        assert_eq!(
+            TimeSignature::additive(vec![u32::MAX, 1], 8),
+            Err(NewTimeSignatureError::TooLong)
+        );
+    }
 }
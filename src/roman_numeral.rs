@@ -0,0 +1,887 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    error::Error,
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::{
+    harmony::{self, ChordQuality, Inversion},
+    interval::{Octave, Semitones},
+    key, pitch,
+    scale::{Mode, Scale},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A degree of a key numbered from 1 for the tonic, raised or lowered by a number of semitones.
+///
+/// In minor keys, numerals on the seventh degree with a minor or diminished quality are measured
+/// from the leading tone rather than the subtonic, so `viio7` and `VII` both need no accidental.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScaleDegree {
+    number: u8,
+    alteration: Semitones,
+}
+
+impl ScaleDegree {
+    /// Returns `None` unless `number` is from 1 to 7.
+    #[must_use]
+    pub fn new(number: u8, alteration: Semitones) -> Option<Self> {
+        (1..=7)
+            .contains(&number)
+            .then_some(Self { number, alteration })
+    }
+
+    #[must_use]
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Semitones up from the degree of the key, e.g. -1 for the `b` of `bVI`.
+    #[must_use]
+    pub fn alteration(&self) -> Semitones {
+        self.alteration
+    }
+}
+
+/// The chord qualities a Roman numeral can express through its case and symbols.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NumeralQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+}
+
+impl NumeralQuality {
+    #[must_use]
+    pub fn chord_quality(self) -> ChordQuality {
+        match self {
+            Self::Major => ChordQuality::Major,
+            Self::Minor => ChordQuality::Minor,
+            Self::Diminished => ChordQuality::Diminished,
+            Self::Augmented => ChordQuality::Augmented,
+            Self::Dominant7 => ChordQuality::Dominant7,
+            Self::Major7 => ChordQuality::Major7,
+            Self::Minor7 => ChordQuality::Minor7,
+            Self::MinorMajor7 => ChordQuality::MinorMajor7,
+            Self::HalfDiminished7 => ChordQuality::HalfDiminished7,
+            Self::Diminished7 => ChordQuality::Diminished7,
+        }
+    }
+
+    /// The numeral quality for a chord quality, or `None` for qualities such as suspended or
+    /// ninth chords that Roman numerals do not express.
+    #[must_use]
+    pub fn from_chord_quality(quality: ChordQuality) -> Option<Self> {
+        Some(match quality {
+            ChordQuality::Major => Self::Major,
+            ChordQuality::Minor => Self::Minor,
+            ChordQuality::Diminished => Self::Diminished,
+            ChordQuality::Augmented => Self::Augmented,
+            ChordQuality::Dominant7 => Self::Dominant7,
+            ChordQuality::Major7 => Self::Major7,
+            ChordQuality::Minor7 => Self::Minor7,
+            ChordQuality::MinorMajor7 => Self::MinorMajor7,
+            ChordQuality::HalfDiminished7 => Self::HalfDiminished7,
+            ChordQuality::Diminished7 => Self::Diminished7,
+            _ => return None,
+        })
+    }
+
+    #[must_use]
+    pub fn is_seventh(self) -> bool {
+        self.tones().len() == 4
+    }
+
+    /// Whether the numeral is written in capitals, i.e. whether the chord has a major third.
+    #[must_use]
+    pub fn is_major(self) -> bool {
+        matches!(
+            self,
+            Self::Major | Self::Augmented | Self::Dominant7 | Self::Major7
+        )
+    }
+
+    /// Semitones above the root of the root, third, fifth and any seventh.
+    fn tones(self) -> &'static [Semitones] {
+        match self {
+            Self::Major => &[0, 4, 7],
+            Self::Minor => &[0, 3, 7],
+            Self::Diminished => &[0, 3, 6],
+            Self::Augmented => &[0, 4, 8],
+            Self::Dominant7 => &[0, 4, 7, 10],
+            Self::Major7 => &[0, 4, 7, 11],
+            Self::Minor7 => &[0, 3, 7, 10],
+            Self::MinorMajor7 => &[0, 3, 7, 11],
+            Self::HalfDiminished7 => &[0, 3, 6, 10],
+            Self::Diminished7 => &[0, 3, 6, 9],
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Major | Self::Minor | Self::Dominant7 | Self::Minor7 => "",
+            Self::Diminished | Self::Diminished7 => "o",
+            Self::Augmented => "+",
+            Self::Major7 | Self::MinorMajor7 => "M",
+            Self::HalfDiminished7 => "ø",
+        }
+    }
+}
+
+/// The Italian, French and German sixths, written over the lowered sixth degree.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AugmentedSixth {
+    Italian,
+    French,
+    German,
+}
+
+impl AugmentedSixth {
+    pub const ALL: [AugmentedSixth; 3] = [
+        AugmentedSixth::Italian,
+        AugmentedSixth::French,
+        AugmentedSixth::German,
+    ];
+
+    #[must_use]
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Self::Italian => "It",
+            Self::French => "Fr",
+            Self::German => "Ger",
+        }
+    }
+
+    /// Semitones above the lowered sixth degree, which is taken as the root.
+    fn tones(self) -> &'static [Semitones] {
+        match self {
+            Self::Italian => &[0, 4, 10],
+            Self::French => &[0, 4, 6, 10],
+            Self::German => &[0, 4, 7, 10],
+        }
+    }
+}
+
+/// What a Roman numeral stands for, before inversion and tonicization.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NumeralChord {
+    Degree(ScaleDegree, NumeralQuality),
+    /// The major triad on the lowered second degree.
+    Neapolitan,
+    AugmentedSixth(AugmentedSixth),
+}
+
+/// The degree a secondary chord is borrowed from, such as the `V` of `V7/V`, read as the tonic
+/// of a major or minor key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tonicization {
+    degree: ScaleDegree,
+    minor: bool,
+}
+
+impl Tonicization {
+    #[must_use]
+    pub fn new(degree: ScaleDegree, minor: bool) -> Self {
+        Self { degree, minor }
+    }
+
+    #[must_use]
+    pub fn degree(&self) -> ScaleDegree {
+        self.degree
+    }
+
+    #[must_use]
+    pub fn minor(&self) -> bool {
+        self.minor
+    }
+}
+
+/// A chord labelled by its function in a key, such as `ii6`, `V65/V`, `bVI`, `N6` or `Ger+6`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RomanNumeral {
+    chord: NumeralChord,
+    inversion: Inversion,
+    tonicization: Option<Tonicization>,
+}
+
+impl RomanNumeral {
+    /// Returns `None` for a third inversion of a triad.
+    #[must_use]
+    pub fn new(degree: ScaleDegree, quality: NumeralQuality, inversion: Inversion) -> Option<Self> {
+        (inversion < Inversion::Third || quality.is_seventh()).then_some(Self {
+            chord: NumeralChord::Degree(degree, quality),
+            inversion,
+            tonicization: None,
+        })
+    }
+
+    /// Returns `None` for a third inversion.
+    #[must_use]
+    pub fn neapolitan(inversion: Inversion) -> Option<Self> {
+        (inversion < Inversion::Third).then_some(Self {
+            chord: NumeralChord::Neapolitan,
+            inversion,
+            tonicization: None,
+        })
+    }
+
+    #[must_use]
+    pub fn augmented_sixth(kind: AugmentedSixth) -> Self {
+        Self {
+            chord: NumeralChord::AugmentedSixth(kind),
+            inversion: Inversion::Root,
+            tonicization: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_tonicization(mut self, tonicization: Tonicization) -> Self {
+        self.tonicization = Some(tonicization);
+        self
+    }
+
+    #[must_use]
+    pub fn chord(&self) -> NumeralChord {
+        self.chord
+    }
+
+    /// Always [`Inversion::Root`] for augmented sixth chords.
+    #[must_use]
+    pub fn inversion(&self) -> Inversion {
+        self.inversion
+    }
+
+    #[must_use]
+    pub fn tonicization(&self) -> Option<Tonicization> {
+        self.tonicization
+    }
+
+    /// Labels a chord without regard to its bass, so always in root position.
+    ///
+    /// Augmented sixth chords are recognized first, then chords of the key (counting the raised
+    /// sixth and seventh degrees of minor keys), the Neapolitan, secondary dominants and
+    /// leading-tone chords of the major and minor triads of the key, and finally chords on
+    /// chromatic degrees, such as those borrowed from the parallel key. Returns `None` when the
+    /// chord has no quality a numeral can express.
+    #[must_use]
+    pub fn from_rooted_chord_class(
+        chord: &harmony::RootedChordClass,
+        key: &key::Key,
+    ) -> Option<Self> {
+        let tonality = Tonality::of(*key);
+        let classes = chord.chord_class().note_pitch_classes();
+        if let Some(kind) = tonality.augmented_sixth(classes) {
+            return Some(Self::augmented_sixth(kind));
+        }
+        let quality = NumeralQuality::from_chord_quality(chord.quality()?)?;
+        Some(tonality.label(chord.root(), quality, classes, Inversion::Root))
+    }
+
+    /// Labels a voiced chord with the figures of its inversion, reading it as in
+    /// [`RomanNumeral::from_rooted_chord_class`]. Augmented sixth chords must have the lowered
+    /// sixth degree in the bass.
+    #[must_use]
+    pub fn from_chord(chord: &harmony::Chord, key: &key::Key) -> Option<Self> {
+        let tonality = Tonality::of(*key);
+        let bass = chord.bass()?.class();
+        let chord_class = chord.chord_class();
+        let classes = chord_class.note_pitch_classes();
+        if let Some(kind) = tonality.augmented_sixth(classes) {
+            if bass == transpose(tonality.tonic, 8) {
+                return Some(Self::augmented_sixth(kind));
+            }
+        }
+        let (quality, inversion, root) = chord.identify().into_iter().find_map(|reading| {
+            Some((
+                NumeralQuality::from_chord_quality(reading.quality())?,
+                reading.inversion()?,
+                reading.root(),
+            ))
+        })?;
+        Some(tonality.label(root, quality, classes, inversion))
+    }
+
+    /// The root and pitch classes of the chord the numeral stands for in `key`. The root of an
+    /// augmented sixth chord is taken to be its lowered sixth degree.
+    #[must_use]
+    pub fn rooted_chord_class(&self, key: &key::Key) -> harmony::RootedChordClass {
+        let (root, tones) = self.root_and_tones(*key);
+        let classes = tones.iter().map(|tone| transpose(root, *tone)).collect();
+        harmony::RootedChordClass::new(harmony::ChordClass::new(classes), root)
+            .unwrap_or_else(|_| unreachable!("every chord includes its root"))
+    }
+
+    /// The pitch class in the bass.
+    #[must_use]
+    pub fn bass(&self, key: &key::Key) -> pitch::NotePitchClass {
+        let (root, tones) = self.root_and_tones(*key);
+        transpose(root, tones[self.inversion as usize])
+    }
+
+    /// The chord in close position with its bass in `octave`.
+    #[must_use]
+    pub fn voiced_chord(&self, key: &key::Key, octave: Octave) -> harmony::Chord {
+        let bass = pitch::NotePitch::new(self.bass(key), octave);
+        let bass_semitones = bass.semitones();
+        let note_pitches: BTreeSet<_> = self
+            .rooted_chord_class(key)
+            .chord_class()
+            .note_pitch_classes()
+            .iter()
+            .map(|class| {
+                let semitones =
+                    bass_semitones + (*class as i32 - bass.class() as i32).rem_euclid(12);
+                pitch::NotePitch::new(*class, semitones.div_euclid(12))
+            })
+            .collect();
+        harmony::Chord::new(note_pitches)
+    }
+
+    fn root_and_tones(&self, key: key::Key) -> (pitch::NotePitchClass, &'static [Semitones]) {
+        let mut tonality = Tonality::of(key);
+        if let Some(tonicization) = self.tonicization {
+            tonality = Tonality {
+                tonic: tonality.degree_class(tonicization.degree, tonicization.minor),
+                mode: if tonicization.minor {
+                    Mode::Aeolian
+                } else {
+                    Mode::Ionian
+                },
+            };
+        }
+        match self.chord {
+            NumeralChord::Degree(degree, quality) => (
+                tonality.degree_class(degree, !quality.is_major()),
+                quality.tones(),
+            ),
+            NumeralChord::Neapolitan => {
+                (transpose(tonality.tonic, 1), NumeralQuality::Major.tones())
+            }
+            NumeralChord::AugmentedSixth(kind) => (transpose(tonality.tonic, 8), kind.tones()),
+        }
+    }
+}
+
+fn transpose(class: pitch::NotePitchClass, semitones: Semitones) -> pitch::NotePitchClass {
+    pitch::NotePitchClass::from_semitones(class as i32 + semitones)
+}
+
+/// The figures for an inversion, e.g. `65` for a seventh chord in first inversion.
+fn figure(inversion: Inversion, seventh: bool) -> &'static str {
+    match (inversion, seventh) {
+        (Inversion::Root, false) => "",
+        (Inversion::First, false) => "6",
+        (Inversion::Second, false) => "64",
+        (Inversion::Root, true) => "7",
+        (Inversion::First, true) => "65",
+        (Inversion::Second, true) => "43",
+        (Inversion::Third, _) => "42",
+    }
+}
+
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+fn write_degree(
+    f: &mut std::fmt::Formatter<'_>,
+    degree: ScaleDegree,
+    major: bool,
+) -> std::fmt::Result {
+    let accidental = if degree.alteration < 0 { "b" } else { "#" };
+    for _ in 0..degree.alteration.unsigned_abs() {
+        f.write_str(accidental)?;
+    }
+    let numeral = NUMERALS[usize::from(degree.number - 1)];
+    if major {
+        f.write_str(numeral)
+    } else {
+        f.write_str(&numeral.to_lowercase())
+    }
+}
+
+impl Display for RomanNumeral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.chord {
+            NumeralChord::Degree(degree, quality) => {
+                write_degree(f, degree, quality.is_major())?;
+                write!(
+                    f,
+                    "{}{}",
+                    quality.symbol(),
+                    figure(self.inversion, quality.is_seventh())
+                )?;
+            }
+            NumeralChord::Neapolitan => write!(f, "N{}", figure(self.inversion, false))?,
+            NumeralChord::AugmentedSixth(kind) => write!(f, "{}+6", kind.abbreviation())?,
+        }
+        if let Some(tonicization) = self.tonicization {
+            f.write_str("/")?;
+            write_degree(f, tonicization.degree, !tonicization.minor)?;
+        }
+        Ok(())
+    }
+}
+
+/// A key reduced to what numerals need, so that tonicized keys need no spelling.
+#[derive(Clone, Copy)]
+struct Tonality {
+    tonic: pitch::NotePitchClass,
+    mode: Mode,
+}
+
+impl Tonality {
+    fn of(key: key::Key) -> Self {
+        Self {
+            tonic: key.tonic().note_pitch_class(),
+            mode: key.mode(),
+        }
+    }
+
+    fn is_minor(self) -> bool {
+        self.mode == Mode::Aeolian
+    }
+
+    fn scale(self) -> Scale {
+        Scale::new(self.tonic, self.mode.pattern())
+    }
+
+    fn degree_class(self, degree: ScaleDegree, lowercase: bool) -> pitch::NotePitchClass {
+        let natural = self
+            .scale()
+            .degree(usize::from(degree.number))
+            .unwrap_or_else(|| unreachable!("degrees are numbered from 1"));
+        let leading_tone = self.is_minor() && degree.number == 7 && lowercase;
+        transpose(natural, degree.alteration + i32::from(leading_tone))
+    }
+
+    /// The degree `class` is written on. Chromatic roots are written as lowered degrees, except
+    /// for the raised fourth and, in minor keys, the raised sixth and seventh.
+    fn degree_of(self, class: pitch::NotePitchClass, lowercase: bool) -> ScaleDegree {
+        let degrees = self.scale().degrees();
+        let position = |class| {
+            degrees
+                .iter()
+                .position(|degree| *degree == class)
+                .and_then(|index| u8::try_from(index + 1).ok())
+        };
+        let flat = position(transpose(class, 1)).map(|number| (number, -1));
+        let sharp = position(transpose(class, -1)).map(|number| (number, 1));
+        let (number, mut alteration) = position(class)
+            .map(|number| (number, 0))
+            .or(match (flat, sharp) {
+                (Some((5, _)), Some(sharp)) => Some(sharp),
+                (_, Some((6 | 7, _))) if self.is_minor() => sharp,
+                _ => flat.or(sharp),
+            })
+            .unwrap_or_else(|| unreachable!("modes have no gaps of more than a whole tone"));
+        if self.is_minor() && number == 7 && lowercase {
+            alteration -= 1;
+        }
+        ScaleDegree { number, alteration }
+    }
+
+    fn is_diatonic(self, classes: &HashSet<pitch::NotePitchClass>) -> bool {
+        let scale = self.scale();
+        classes.iter().all(|class| {
+            scale.contains(*class)
+                || (self.is_minor()
+                    && [9, 11].contains(&(*class as i32 - self.tonic as i32).rem_euclid(12)))
+        })
+    }
+
+    /// Whether the triad on a degree is minor, or `None` when it is neither major nor minor.
+    /// The dominant of a minor key counts as major.
+    fn triad_is_minor(self, number: usize) -> Option<bool> {
+        let scale = self.scale();
+        let degree = |steps| scale.degree(number + steps).map(|class| class as i32);
+        let root = degree(0)?;
+        let third = (degree(2)? - root).rem_euclid(12);
+        let fifth = (degree(4)? - root).rem_euclid(12);
+        match (third, fifth) {
+            (_, 7) if self.is_minor() && number == 5 => Some(false),
+            (4, 7) => Some(false),
+            (3, 7) => Some(true),
+            _ => None,
+        }
+    }
+
+    fn augmented_sixth(self, classes: &HashSet<pitch::NotePitchClass>) -> Option<AugmentedSixth> {
+        let root = transpose(self.tonic, 8);
+        AugmentedSixth::ALL.into_iter().find(|kind| {
+            kind.tones().len() == classes.len()
+                && kind
+                    .tones()
+                    .iter()
+                    .all(|tone| classes.contains(&transpose(root, *tone)))
+        })
+    }
+
+    fn label(
+        self,
+        root: pitch::NotePitchClass,
+        quality: NumeralQuality,
+        classes: &HashSet<pitch::NotePitchClass>,
+        inversion: Inversion,
+    ) -> RomanNumeral {
+        let lowercase = !quality.is_major();
+        let numeral = |degree| RomanNumeral {
+            chord: NumeralChord::Degree(degree, quality),
+            inversion,
+            tonicization: None,
+        };
+        if self.is_diatonic(classes) {
+            return numeral(self.degree_of(root, lowercase));
+        }
+        if quality == NumeralQuality::Major && root == transpose(self.tonic, 1) {
+            return RomanNumeral {
+                chord: NumeralChord::Neapolitan,
+                inversion,
+                tonicization: None,
+            };
+        }
+
+        let target = match quality {
+            NumeralQuality::Major | NumeralQuality::Dominant7 => Some(transpose(root, 5)),
+            NumeralQuality::Diminished
+            | NumeralQuality::Diminished7
+            | NumeralQuality::HalfDiminished7 => Some(transpose(root, 1)),
+            _ => None,
+        };
+        let tonicized = target.and_then(|target| {
+            let number = self
+                .scale()
+                .degree_of(target)
+                .filter(|number| *number > 1)?;
+            let minor = self.triad_is_minor(number)?;
+            let tonicized = Self {
+                tonic: target,
+                mode: if minor { Mode::Aeolian } else { Mode::Ionian },
+            };
+            let number = u8::try_from(number).ok()?;
+            Some(
+                numeral(tonicized.degree_of(root, lowercase)).with_tonicization(Tonicization {
+                    degree: ScaleDegree {
+                        number,
+                        alteration: 0,
+                    },
+                    minor,
+                }),
+            )
+        });
+        tonicized.unwrap_or_else(|| numeral(self.degree_of(root, lowercase)))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseRomanNumeralErrorKind {
+    Empty,
+    InvalidNumeral,
+    InvalidQuality,
+    InvalidFigure,
+    InvalidTonicization,
+}
+
+/// Why a Roman numeral could not be parsed, and the character, counted from zero, at which the
+/// problem starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParseRomanNumeralError {
+    kind: ParseRomanNumeralErrorKind,
+    position: usize,
+}
+
+impl ParseRomanNumeralError {
+    fn at(kind: ParseRomanNumeralErrorKind, text: &str, byte: usize) -> Self {
+        Self {
+            kind,
+            position: text[..byte].chars().count(),
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> ParseRomanNumeralErrorKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for ParseRomanNumeralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.kind {
+            ParseRomanNumeralErrorKind::Empty => "empty Roman numeral",
+            ParseRomanNumeralErrorKind::InvalidNumeral => "invalid numeral",
+            ParseRomanNumeralErrorKind::InvalidQuality => "invalid chord quality",
+            ParseRomanNumeralErrorKind::InvalidFigure => "invalid inversion figure",
+            ParseRomanNumeralErrorKind::InvalidTonicization => "invalid tonicized degree",
+        })?;
+        write!(f, " at character {}", self.position)
+    }
+}
+
+impl Error for ParseRomanNumeralError {}
+
+/// Parses the accidentals and numeral at the start of `text`, returning the degree, whether the
+/// numeral is in capitals and the number of bytes taken up.
+fn parse_degree_prefix(text: &str) -> Option<(ScaleDegree, bool, usize)> {
+    let accidentals = text.len() - text.trim_start_matches(['b', '#']).len();
+    let alteration = text[..accidentals]
+        .chars()
+        .map(|accidental| if accidental == 'b' { -1 } else { 1 })
+        .sum();
+    let rest = &text[accidentals..];
+    // the longest match, so that VII is not read as V
+    let (index, numeral, major) = NUMERALS
+        .iter()
+        .enumerate()
+        .flat_map(|(index, numeral)| {
+            [
+                (index, (*numeral).to_owned(), true),
+                (index, numeral.to_lowercase(), false),
+            ]
+        })
+        .filter(|(_, numeral, _)| rest.starts_with(numeral.as_str()))
+        .max_by_key(|(_, numeral, _)| numeral.len())?;
+    let number = u8::try_from(index + 1).ok()?;
+    Some((
+        ScaleDegree { number, alteration },
+        major,
+        accidentals + numeral.len(),
+    ))
+}
+
+fn parse_figure(figure: &str, seventh: bool) -> Option<Inversion> {
+    [
+        Inversion::Root,
+        Inversion::First,
+        Inversion::Second,
+        Inversion::Third,
+    ]
+    .into_iter()
+    .find(|inversion| {
+        (*inversion < Inversion::Third || seventh) && self::figure(*inversion, seventh) == figure
+    })
+    .or_else(|| (seventh && figure == "2").then_some(Inversion::Third))
+}
+
+impl FromStr for RomanNumeral {
+    type Err = ParseRomanNumeralError;
+
+    /// Parses labels such as `I`, `ii6`, `V65`, `viio7`, `viiø43`, `bVI`, `IVM7`, `III+`, `N6`,
+    /// `It+6`, `Fr+6`, `Ger+6` and secondary chords such as `V7/V` or `viio7/ii`. Augmented
+    /// sixths may also be written with their figured bass, as in `Fr43` or `Ger65`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ParseRomanNumeralErrorKind as Kind;
+
+        if s.is_empty() {
+            return Err(ParseRomanNumeralError::at(Kind::Empty, s, 0));
+        }
+        let end = s.find('/').unwrap_or(s.len());
+        let label = &s[..end];
+
+        let mut numeral = if let Some(kind) = AugmentedSixth::ALL
+            .into_iter()
+            .find(|kind| label.starts_with(kind.abbreviation()))
+        {
+            let start = kind.abbreviation().len();
+            let figure = label[start..].trim_start_matches('+');
+            if !["", "6", "43", "65"].contains(&figure) {
+                return Err(ParseRomanNumeralError::at(Kind::InvalidFigure, s, start));
+            }
+            Self::augmented_sixth(kind)
+        } else if let Some(figure) = label.strip_prefix('N') {
+            parse_figure(figure, false)
+                .and_then(Self::neapolitan)
+                .ok_or_else(|| ParseRomanNumeralError::at(Kind::InvalidFigure, s, 1))?
+        } else {
+            let (degree, major, mut index) = parse_degree_prefix(label)
+                .ok_or_else(|| ParseRomanNumeralError::at(Kind::InvalidNumeral, s, 0))?;
+            let symbol = ["o", "°", "ø", "+", "M"]
+                .into_iter()
+                .find(|symbol| label[index..].starts_with(symbol))
+                .unwrap_or("");
+            let symbol_start = index;
+            index += symbol.len();
+            let figure = &label[index..];
+            let seventh = !matches!(figure, "" | "6" | "64");
+            let inversion = parse_figure(figure, seventh)
+                .ok_or_else(|| ParseRomanNumeralError::at(Kind::InvalidFigure, s, index))?;
+            let quality = match (major, symbol, seventh) {
+                (true, "", false) => NumeralQuality::Major,
+                (false, "", false) => NumeralQuality::Minor,
+                (false, "o" | "°", false) => NumeralQuality::Diminished,
+                (true, "+", false) => NumeralQuality::Augmented,
+                (true, "", true) => NumeralQuality::Dominant7,
+                (true, "M", true) => NumeralQuality::Major7,
+                (false, "", true) => NumeralQuality::Minor7,
+                (false, "M", true) => NumeralQuality::MinorMajor7,
+                (false, "ø", true) => NumeralQuality::HalfDiminished7,
+                (false, "o" | "°", true) => NumeralQuality::Diminished7,
+                _ => {
+                    return Err(ParseRomanNumeralError::at(
+                        Kind::InvalidQuality,
+                        s,
+                        symbol_start,
+                    ))
+                }
+            };
+            Self::new(degree, quality, inversion)
+                .unwrap_or_else(|| unreachable!("triads are never given a third inversion"))
+        };
+
+        if end < s.len() {
+            let target = &s[end + 1..];
+            let tonicization = parse_degree_prefix(target)
+                .filter(|(_, _, length)| *length == target.len())
+                .map(|(degree, major, _)| Tonicization::new(degree, !major))
+                .ok_or_else(|| ParseRomanNumeralError::at(Kind::InvalidTonicization, s, end + 1))?;
+            numeral = numeral.with_tonicization(tonicization);
+        }
+        Ok(numeral)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::{NotePitch, NotePitchClass, SpelledNotePitchClass};
+
+    use super::*;
+
+    fn key(tonic: &str, minor: bool) -> key::Key {
+        let tonic = SpelledNotePitchClass::from_str(tonic).unwrap();
+        if minor {
+            key::Key::minor(tonic).unwrap()
+        } else {
+            key::Key::major(tonic).unwrap()
+        }
+    }
+
+    fn label(note_pitches: &[(NotePitchClass, i32)], key: key::Key) -> String {
+        let chord = harmony::Chord::new(
+            note_pitches
+                .iter()
+                .map(|(class, octave)| NotePitch::new(*class, *octave))
+                .collect(),
+        );
+        RomanNumeral::from_chord(&chord, &key).unwrap().to_string()
+    }
+
+    #[test]
+    fn label_chords() {
+        use NotePitchClass::{As, Cs, Ds, Fs, Gs, A, B, C, D, E, F, G};
+        let c_major = key("C", false);
+        let diatonic: Vec<_> = c_major
+            .diatonic_seventh_chords()
+            .iter()
+            .map(|chord| {
+                RomanNumeral::from_rooted_chord_class(chord, &c_major)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            diatonic,
+            ["IM7", "ii7", "iii7", "IVM7", "V7", "vi7", "viiø7"]
+        );
+
+        assert_eq!(label(&[(C, 3), (E, 3), (G, 3)], c_major), "I");
+        assert_eq!(label(&[(F, 3), (A, 3), (D, 4)], c_major), "ii6");
+        assert_eq!(label(&[(B, 2), (D, 3), (F, 3), (G, 3)], c_major), "V65");
+        assert_eq!(label(&[(F, 3), (G, 3), (B, 3), (D, 4)], c_major), "V42");
+        assert_eq!(label(&[(Gs, 2), (C, 3), (Ds, 3)], c_major), "bVI");
+        assert_eq!(label(&[(D, 3), (Fs, 3), (A, 3)], c_major), "V/V");
+        assert_eq!(
+            label(&[(Fs, 3), (A, 3), (C, 4), (Ds, 4)], c_major),
+            "viio7/V"
+        );
+        assert_eq!(label(&[(Gs, 2), (C, 4), (Fs, 4)], c_major), "It+6");
+        assert_eq!(label(&[(Gs, 2), (C, 4), (D, 4), (Fs, 4)], c_major), "Fr+6");
+        assert_eq!(
+            label(&[(Gs, 2), (C, 4), (Ds, 4), (Fs, 4)], c_major),
+            "Ger+6"
+        );
+        assert_eq!(label(&[(F, 3), (Gs, 3), (Cs, 4)], c_major), "N6");
+
+        let c_minor = key("C", true);
+        assert_eq!(label(&[(G, 2), (B, 3), (D, 4)], c_minor), "V");
+        assert_eq!(label(&[(B, 2), (D, 3), (F, 3), (Gs, 3)], c_minor), "viio7");
+        assert_eq!(label(&[(Gs, 2), (C, 3), (Ds, 3)], c_minor), "VI");
+        assert_eq!(label(&[(As, 2), (D, 3), (F, 3)], c_minor), "VII");
+        assert_eq!(label(&[(D, 3), (Fs, 3), (A, 3), (C, 4)], c_minor), "V7/V");
+    }
+
+    #[test]
+    fn parse_labels() {
+        let c_major = key("C", false);
+        for label in [
+            "I", "ii6", "V65", "viio7", "viiø43", "bVI", "V7/V", "V/V", "IVM7", "III+6", "N6",
+            "It+6", "Fr+6", "Ger+6", "viio7/ii", "V42/IV",
+        ] {
+            assert_eq!(label.parse::<RomanNumeral>().unwrap().to_string(), label);
+        }
+        assert_eq!(
+            "Ger65".parse::<RomanNumeral>().unwrap(),
+            RomanNumeral::augmented_sixth(AugmentedSixth::German)
+        );
+
+        let secondary: RomanNumeral = "V65/V".parse().unwrap();
+        assert_eq!(secondary.bass(&c_major), NotePitchClass::Fs);
+        assert_eq!(
+            secondary.rooted_chord_class(&c_major).root(),
+            NotePitchClass::D
+        );
+        let neapolitan: RomanNumeral = "N6".parse().unwrap();
+        assert_eq!(
+            neapolitan.voiced_chord(&key("C", true), 3),
+            harmony::Chord::new(
+                [
+                    NotePitch::new(NotePitchClass::F, 3),
+                    NotePitch::new(NotePitchClass::Gs, 3),
+                    NotePitch::new(NotePitchClass::Cs, 4),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        let leading_tone: RomanNumeral = "viio7".parse().unwrap();
+        assert_eq!(
+            leading_tone.rooted_chord_class(&key("C", true)).root(),
+            NotePitchClass::B
+        );
+
+        let error = |label: &str| {
+            let error = label.parse::<RomanNumeral>().unwrap_err();
+            (error.kind(), error.position())
+        };
+        assert_eq!(error(""), (ParseRomanNumeralErrorKind::Empty, 0));
+        assert_eq!(error("X"), (ParseRomanNumeralErrorKind::InvalidNumeral, 0));
+        assert_eq!(error("V9"), (ParseRomanNumeralErrorKind::InvalidFigure, 1));
+        assert_eq!(
+            error("Vø7"),
+            (ParseRomanNumeralErrorKind::InvalidQuality, 1)
+        );
+        assert_eq!(
+            error("V/X"),
+            (ParseRomanNumeralErrorKind::InvalidTonicization, 2)
+        );
+    }
+}
@@ -0,0 +1,746 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::pitch::{self, NotePitch, Tuning, TuningReference, TuningSystem};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One degree of a Scala scale, written either in cents or as a frequency ratio above the first
+/// degree.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScalaPitch {
+    Cents(f64),
+    Ratio(u32, u32),
+}
+
+impl ScalaPitch {
+    #[must_use]
+    pub fn cents(self) -> f64 {
+        match self {
+            Self::Cents(cents) => cents,
+            Self::Ratio(numerator, denominator) => {
+                1200.0 * (f64::from(numerator) / f64::from(denominator)).log2()
+            }
+        }
+    }
+}
+
+impl Display for ScalaPitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // the decimal point is what marks a value as cents
+            Self::Cents(cents) => write!(f, "{cents:.5}"),
+            Self::Ratio(numerator, denominator) => write!(f, "{numerator}/{denominator}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewScalaScaleError {
+    NoPitches,
+    NonPositiveRatio,
+}
+
+impl Display for NewScalaScaleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NoPitches => "a scale needs at least one pitch",
+            Self::NonPositiveRatio => "ratios must be positive",
+        })
+    }
+}
+
+impl Error for NewScalaScaleError {}
+
+/// The contents of a Scala `.scl` file: the degrees of a scale above its first degree, which is
+/// left out, ending with the interval the scale repeats at (usually `2/1`).
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScalaScale {
+    description: String,
+    pitches: Vec<ScalaPitch>,
+}
+
+impl ScalaScale {
+    /// # Errors
+    ///
+    /// Returns a [`NewScalaScaleError`] if there are no pitches or a ratio has a zero term.
+    pub fn new(description: String, pitches: Vec<ScalaPitch>) -> Result<Self, NewScalaScaleError> {
+        if pitches.is_empty() {
+            return Err(NewScalaScaleError::NoPitches);
+        }
+        let non_positive = |pitch: &ScalaPitch| match pitch {
+            ScalaPitch::Ratio(numerator, denominator) => *numerator == 0 || *denominator == 0,
+            ScalaPitch::Cents(_) => false,
+        };
+        if pitches.iter().any(non_positive) {
+            return Err(NewScalaScaleError::NonPositiveRatio);
+        }
+        Ok(Self {
+            description,
+            pitches,
+        })
+    }
+
+    /// The scale a built-in tuning system lays out above its tonic.
+    #[must_use]
+    pub fn from_tuning_system(tuning: &TuningSystem) -> Self {
+        let description = match tuning {
+            TuningSystem::EqualTempered => "12-tone equal temperament".to_owned(),
+            TuningSystem::Pythagorean(_) => "Pythagorean tuning".to_owned(),
+            TuningSystem::QuarterCommaMeantone(_) => "1/4-comma meantone".to_owned(),
+            TuningSystem::JustIntonation(_) => "5-limit just intonation".to_owned(),
+            TuningSystem::WerckmeisterIII(_) => "Werckmeister III".to_owned(),
+            TuningSystem::KirnbergerIII(_) => "Kirnberger III".to_owned(),
+            TuningSystem::Vallotti(_) => "Vallotti".to_owned(),
+            TuningSystem::Edo { divisions, .. } => {
+                format!("{divisions} equal divisions of the octave")
+            }
+        };
+        let octave = ScalaPitch::Ratio(2, 1);
+        let pitches = match tuning {
+            TuningSystem::JustIntonation(_) => pitch::JUST_INTONATION_RATIOS[1..]
+                .iter()
+                .map(|(numerator, denominator)| ScalaPitch::Ratio(*numerator, *denominator))
+                .chain([octave])
+                .collect(),
+            TuningSystem::Edo { divisions, .. } => (1..divisions.get())
+                .map(|step| {
+                    ScalaPitch::Cents(f64::from(step) * 1200.0 / f64::from(divisions.get()))
+                })
+                .chain([octave])
+                .collect(),
+            _ => tuning.octave_cents()[1..]
+                .iter()
+                .map(|cents| ScalaPitch::Cents(*cents))
+                .chain([octave])
+                .collect(),
+        };
+        Self {
+            description,
+            pitches,
+        }
+    }
+
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    #[must_use]
+    pub fn pitches(&self) -> &[ScalaPitch] {
+        &self.pitches
+    }
+
+    /// The number of notes in the scale, counting the first degree but not its repetition.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pitches.len()
+    }
+
+    /// Always `false`; a scale has at least one pitch.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pitches.is_empty()
+    }
+
+    /// The interval the scale repeats at.
+    #[must_use]
+    pub fn period_cents(&self) -> f64 {
+        self.pitches[self.pitches.len() - 1].cents()
+    }
+
+    /// The cents above the first degree of `degree`, where degree 0 is the first degree and
+    /// degrees outside the scale repeat it at its period.
+    #[must_use]
+    pub fn degree_cents(&self, degree: i32) -> f64 {
+        let len = i32::try_from(self.pitches.len()).unwrap_or(i32::MAX);
+        let index = degree.rem_euclid(len) as usize;
+        let within_period = index
+            .checked_sub(1)
+            .map_or(0.0, |index| self.pitches[index].cents());
+        f64::from(degree.div_euclid(len)) * self.period_cents() + within_period
+    }
+}
+
+/// Writes the scale in the `.scl` format. The description must fit on one line.
+impl Display for ScalaScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.description)?;
+        writeln!(f, " {}", self.pitches.len())?;
+        writeln!(f, "!")?;
+        for pitch in &self.pitches {
+            writeln!(f, " {pitch}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParseScalaErrorKind {
+    MissingDescription,
+    MissingNoteCount,
+    InvalidNoteCount,
+    NoPitches,
+    MissingPitch,
+    InvalidPitch,
+    NonPositiveRatio,
+    MissingField,
+    InvalidField,
+    /// A key number outside the MIDI range 0..=127.
+    InvalidKey,
+    InvalidFrequency,
+    InvalidMappingEntry,
+}
+
+/// Why a Scala file could not be parsed, and the line, counted from one, where the problem is.
+/// Missing content is reported on the line after the last.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParseScalaError {
+    kind: ParseScalaErrorKind,
+    line: usize,
+}
+
+impl ParseScalaError {
+    #[must_use]
+    pub fn kind(&self) -> ParseScalaErrorKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Display for ParseScalaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        f.write_str(match self.kind {
+            ParseScalaErrorKind::MissingDescription => "missing description",
+            ParseScalaErrorKind::MissingNoteCount => "missing number of notes",
+            ParseScalaErrorKind::InvalidNoteCount => "invalid number of notes",
+            ParseScalaErrorKind::NoPitches => "a scale needs at least one pitch",
+            ParseScalaErrorKind::MissingPitch => "fewer pitches than the number of notes",
+            ParseScalaErrorKind::InvalidPitch => "invalid pitch",
+            ParseScalaErrorKind::NonPositiveRatio => "ratios must be positive",
+            ParseScalaErrorKind::MissingField => "missing keyboard mapping field",
+            ParseScalaErrorKind::InvalidField => "invalid keyboard mapping field",
+            ParseScalaErrorKind::InvalidKey => "invalid key number",
+            ParseScalaErrorKind::InvalidFrequency => "invalid reference frequency",
+            ParseScalaErrorKind::InvalidMappingEntry => "invalid keyboard mapping entry",
+        })
+    }
+}
+
+impl Error for ParseScalaError {}
+
+/// The lines of a Scala file that are not comments, with their line numbers, and the line number
+/// to report missing content at.
+fn content_lines(text: &str) -> (impl Iterator<Item = (usize, &str)>, usize) {
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.starts_with('!'));
+    (lines, text.lines().count() + 1)
+}
+
+fn parse_pitch(text: &str) -> Result<ScalaPitch, ParseScalaErrorKind> {
+    let token = text
+        .split_whitespace()
+        .next()
+        .ok_or(ParseScalaErrorKind::InvalidPitch)?;
+    if token.contains('.') {
+        return token
+            .parse()
+            .ok()
+            .filter(|cents: &f64| cents.is_finite())
+            .map(ScalaPitch::Cents)
+            .ok_or(ParseScalaErrorKind::InvalidPitch);
+    }
+    if token.starts_with('-') {
+        return Err(ParseScalaErrorKind::NonPositiveRatio);
+    }
+    let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+    let term = |term: &str| {
+        term.parse::<u32>()
+            .map_err(|_| ParseScalaErrorKind::InvalidPitch)
+    };
+    let (numerator, denominator) = (term(numerator)?, term(denominator)?);
+    if numerator == 0 || denominator == 0 {
+        return Err(ParseScalaErrorKind::NonPositiveRatio);
+    }
+    Ok(ScalaPitch::Ratio(numerator, denominator))
+}
+
+/// Parses a `.scl` file. Lines starting with `!` are comments; the first other line is the
+/// description, the next the number of pitches, and then one pitch per line, where a value
+/// with a decimal point is in cents and any other is a ratio such as `3/2` or `2`.
+impl FromStr for ScalaScale {
+    type Err = ParseScalaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let at = |kind, line| ParseScalaError { kind, line };
+        let (mut lines, end) = content_lines(s);
+        let (_, description) = lines
+            .next()
+            .ok_or(at(ParseScalaErrorKind::MissingDescription, end))?;
+        let (count_line, count) = lines
+            .next()
+            .ok_or(at(ParseScalaErrorKind::MissingNoteCount, end))?;
+        let count: usize = count
+            .split_whitespace()
+            .next()
+            .and_then(|count| count.parse().ok())
+            .ok_or(at(ParseScalaErrorKind::InvalidNoteCount, count_line))?;
+        if count == 0 {
+            return Err(at(ParseScalaErrorKind::NoPitches, count_line));
+        }
+        let pitches = (0..count)
+            .map(|_| {
+                let (line, text) = lines
+                    .next()
+                    .ok_or(at(ParseScalaErrorKind::MissingPitch, end))?;
+                parse_pitch(text).map_err(|kind| at(kind, line))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            description: description.trim().to_owned(),
+            pitches,
+        })
+    }
+}
+
+/// The contents of a Scala `.kbm` file, which says which scale degree each MIDI key plays and
+/// which key sounds at a reference frequency.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyboardMapping {
+    first_key: u8,
+    last_key: u8,
+    middle_key: u8,
+    reference_key: u8,
+    reference_frequency: pitch::Pitch,
+    octave_degree: usize,
+    keys: Vec<Option<usize>>,
+}
+
+impl KeyboardMapping {
+    /// A mapping of consecutive keys to consecutive scale degrees, with the first degree on
+    /// `middle_key` and `reference_key` sounding at `reference_frequency`.
+    #[must_use]
+    pub fn new(middle_key: u8, reference_key: u8, reference_frequency: pitch::Pitch) -> Self {
+        Self {
+            first_key: 0,
+            last_key: 127,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree: 0,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Limits the keys that are tuned to `first_key..=last_key`.
+    #[must_use]
+    pub fn with_range(self, first_key: u8, last_key: u8) -> Self {
+        Self {
+            first_key,
+            last_key,
+            ..self
+        }
+    }
+
+    /// Repeats a pattern of scale degrees, one per key starting at the middle key, where `None`
+    /// leaves a key silent. Each repetition is transposed by the interval of `octave_degree`.
+    #[must_use]
+    pub fn with_keys(self, keys: Vec<Option<usize>>, octave_degree: usize) -> Self {
+        Self {
+            octave_degree,
+            keys,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn first_key(&self) -> u8 {
+        self.first_key
+    }
+
+    #[must_use]
+    pub fn last_key(&self) -> u8 {
+        self.last_key
+    }
+
+    #[must_use]
+    pub fn middle_key(&self) -> u8 {
+        self.middle_key
+    }
+
+    #[must_use]
+    pub fn reference_key(&self) -> u8 {
+        self.reference_key
+    }
+
+    #[must_use]
+    pub fn reference_frequency(&self) -> pitch::Pitch {
+        self.reference_frequency
+    }
+
+    #[must_use]
+    pub fn octave_degree(&self) -> usize {
+        self.octave_degree
+    }
+
+    /// The repeating pattern of scale degrees, empty for a linear mapping.
+    #[must_use]
+    pub fn keys(&self) -> &[Option<usize>] {
+        &self.keys
+    }
+
+    /// The cents above the middle key's degree that `key` plays in `scale`, or `None` if the
+    /// key is unmapped.
+    fn key_cents(&self, scale: &ScalaScale, key: i32) -> Option<f64> {
+        let offset = key - i32::from(self.middle_key);
+        if self.keys.is_empty() {
+            return Some(scale.degree_cents(offset));
+        }
+        let size = i32::try_from(self.keys.len()).unwrap_or(i32::MAX);
+        let degree = self.keys[offset.rem_euclid(size) as usize]?;
+        let degree = i32::try_from(degree).ok()?;
+        let octave_degree = i32::try_from(self.octave_degree).ok()?;
+        Some(
+            f64::from(offset.div_euclid(size)) * scale.degree_cents(octave_degree)
+                + scale.degree_cents(degree),
+        )
+    }
+}
+
+/// Middle C on the first degree and A4 at 440 Hz, as Scala itself assumes without a mapping.
+impl Default for KeyboardMapping {
+    fn default() -> Self {
+        Self::new(60, 69, 440.0)
+    }
+}
+
+/// Writes the mapping in the `.kbm` format.
+impl Display for KeyboardMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "! Size of map:\n{}", self.keys.len())?;
+        writeln!(f, "! First MIDI note number to retune:\n{}", self.first_key)?;
+        writeln!(f, "! Last MIDI note number to retune:\n{}", self.last_key)?;
+        writeln!(
+            f,
+            "! Middle note where the first entry of the mapping is mapped to:"
+        )?;
+        writeln!(f, "{}", self.middle_key)?;
+        writeln!(f, "! Reference note for which frequency is given:")?;
+        writeln!(f, "{}", self.reference_key)?;
+        writeln!(f, "! Frequency to tune the above note to:")?;
+        writeln!(f, "{:?}", self.reference_frequency)?;
+        writeln!(f, "! Scale degree to consider as formal octave:")?;
+        writeln!(f, "{}", self.octave_degree)?;
+        writeln!(f, "! Mapping:")?;
+        for key in &self.keys {
+            match key {
+                Some(degree) => writeln!(f, "{degree}")?,
+                None => writeln!(f, "x")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `.kbm` file: seven header values, one per line, followed by up to one scale degree
+/// or `x` per key of the map. Keys the file leaves out are unmapped. Lines starting with `!` are
+/// comments and blank lines are ignored.
+impl FromStr for KeyboardMapping {
+    type Err = ParseScalaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let at = |kind, line| ParseScalaError { kind, line };
+        let (lines, end) = content_lines(s);
+        let mut lines = lines.filter(|(_, line)| !line.trim().is_empty());
+        let mut field = |kind| {
+            let (line, text) = lines
+                .next()
+                .ok_or(at(ParseScalaErrorKind::MissingField, end))?;
+            let value = text.split_whitespace().next().unwrap_or_default();
+            Ok::<_, ParseScalaError>((line, value, kind))
+        };
+        let parse_key = |(line, value, kind): (usize, &str, _)| {
+            value
+                .parse::<u8>()
+                .ok()
+                .filter(|key| *key <= 127)
+                .ok_or(at(kind, line))
+        };
+
+        let (size_line, size, _) = field(ParseScalaErrorKind::InvalidField)?;
+        let size: usize = size
+            .parse()
+            .map_err(|_| at(ParseScalaErrorKind::InvalidField, size_line))?;
+        let first_key = parse_key(field(ParseScalaErrorKind::InvalidKey)?)?;
+        let last_key = parse_key(field(ParseScalaErrorKind::InvalidKey)?)?;
+        let middle_key = parse_key(field(ParseScalaErrorKind::InvalidKey)?)?;
+        let reference_key = parse_key(field(ParseScalaErrorKind::InvalidKey)?)?;
+        let (line, frequency, kind) = field(ParseScalaErrorKind::InvalidFrequency)?;
+        let reference_frequency = frequency
+            .parse()
+            .ok()
+            .filter(|frequency: &f64| frequency.is_finite() && *frequency > 0.0)
+            .ok_or(at(kind, line))?;
+        let (line, octave_degree, kind) = field(ParseScalaErrorKind::InvalidField)?;
+        let octave_degree = octave_degree.parse().map_err(|_| at(kind, line))?;
+
+        let mut keys = Vec::with_capacity(size);
+        for _ in 0..size {
+            let Some((line, value, _)) = field(ParseScalaErrorKind::InvalidMappingEntry).ok()
+            else {
+                keys.push(None);
+                continue;
+            };
+            keys.push(match value {
+                "x" => None,
+                degree => Some(
+                    degree
+                        .parse()
+                        .map_err(|_| at(ParseScalaErrorKind::InvalidMappingEntry, line))?,
+                ),
+            });
+        }
+        Ok(Self::new(middle_key, reference_key, reference_frequency)
+            .with_range(first_key, last_key)
+            .with_keys(keys, octave_degree))
+    }
+}
+
+/// A Scala scale played through a keyboard mapping. Unlike a [`TuningSystem`], it need not have
+/// twelve notes to the octave and may leave keys unmapped, which then have no pitch.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScalaTuning {
+    scale: ScalaScale,
+    mapping: KeyboardMapping,
+}
+
+impl ScalaTuning {
+    #[must_use]
+    pub fn new(scale: ScalaScale, mapping: KeyboardMapping) -> Self {
+        Self { scale, mapping }
+    }
+
+    #[must_use]
+    pub fn scale(&self) -> &ScalaScale {
+        &self.scale
+    }
+
+    #[must_use]
+    pub fn mapping(&self) -> &KeyboardMapping {
+        &self.mapping
+    }
+}
+
+/// Keys that are unmapped or outside the mapping's range have no pitch.
+impl Tuning for ScalaTuning {
+    /// The reference note and frequency of the mapping, with the middle key's pitch class as the
+    /// tonic.
+    fn reference(&self) -> TuningReference {
+        TuningReference::new(
+            key_note_pitch(self.mapping.reference_key),
+            self.mapping.reference_frequency,
+            key_note_pitch(self.mapping.middle_key).class(),
+        )
+    }
+
+    fn cents_from_reference(&self, note_pitch: &NotePitch) -> Option<pitch::Cents> {
+        let key = note_pitch.semitones() + 12;
+        let range = i32::from(self.mapping.first_key)..=i32::from(self.mapping.last_key);
+        if !range.contains(&key) {
+            return None;
+        }
+        let cents = self.mapping.key_cents(&self.scale, key)?;
+        let reference_cents = self
+            .mapping
+            .key_cents(&self.scale, i32::from(self.mapping.reference_key))?;
+        Some(cents - reference_cents)
+    }
+}
+
+/// The pitch a key number stands for, like [`NotePitch::from_midi_number`] but continuing past
+/// key 127, which mappings built in code may use.
+fn key_note_pitch(key: u8) -> NotePitch {
+    let semitones = i32::from(key);
+    NotePitch::new(
+        pitch::NotePitchClass::from_semitones(semitones),
+        semitones.div_euclid(12) - 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU16;
+
+    use crate::pitch::{NotePitchClass, SpelledNotePitch, ToPitch};
+
+    use super::*;
+
+    const MEANTONE: &str = "! meanquar.scl
+!
+1/4-comma meantone scale. Pietro Aaron's temperament (1523)
+ 12
+!
+ 76.04900
+ 193.15686
+ 310.26286
+ 5/4
+ 503.42157
+ 579.47057
+ 696.57843
+ 25/16
+ 889.73529
+ 1006.84314
+ 1082.89214
+ 2/1
+";
+
+    #[test]
+    fn parse_scl() {
+        let scale: ScalaScale = MEANTONE.parse().unwrap();
+        assert_eq!(
+            scale.description(),
+            "1/4-comma meantone scale. Pietro Aaron's temperament (1523)"
+        );
+        assert_eq!(scale.len(), 12);
+        assert_eq!(scale.pitches()[3], ScalaPitch::Ratio(5, 4));
+        assert!((scale.degree_cents(4) - 386.313_7).abs() < 1e-3);
+        assert!((scale.degree_cents(-12) + 1200.0).abs() < 1e-9);
+        assert!((scale.degree_cents(13) - 1276.049).abs() < 1e-9);
+
+        let reparsed: ScalaScale = scale.to_string().parse().unwrap();
+        assert_eq!(reparsed, scale);
+    }
+
+    #[test]
+    fn scl_errors_have_line_numbers() {
+        let error = |text: &str| {
+            let error = text.parse::<ScalaScale>().unwrap_err();
+            (error.kind(), error.line())
+        };
+        assert_eq!(error(""), (ParseScalaErrorKind::MissingDescription, 1));
+        assert_eq!(
+            error("! comment\nname\n twelve\n"),
+            (ParseScalaErrorKind::InvalidNoteCount, 3)
+        );
+        assert_eq!(
+            error("name\n 2\n 100.0\n"),
+            (ParseScalaErrorKind::MissingPitch, 4)
+        );
+        assert_eq!(
+            error("name\n 2\n!\n 100.0\n 3/0\n"),
+            (ParseScalaErrorKind::NonPositiveRatio, 5)
+        );
+        assert_eq!(
+            error("name\n 1\n cents\n"),
+            (ParseScalaErrorKind::InvalidPitch, 3)
+        );
+    }
+
+    #[test]
+    fn parse_kbm_and_tune() {
+        let mapping: KeyboardMapping = "! a whole-tone keyboard
+6
+0
+127
+60
+65
+432.0
+6
+! Mapping:
+0
+x
+1
+x
+2
+3
+"
+        .parse()
+        .unwrap();
+        assert_eq!(mapping.keys().len(), 6);
+        assert_eq!(mapping.keys()[1], None);
+        assert!((mapping.reference_frequency() - 432.0).abs() < 1e-9);
+
+        let reparsed: KeyboardMapping = mapping.to_string().parse().unwrap();
+        assert_eq!(reparsed, mapping);
+
+        let error = "6\n0\n127\n60\n200\n"
+            .parse::<KeyboardMapping>()
+            .unwrap_err();
+        assert_eq!(
+            (error.kind(), error.line()),
+            (ParseScalaErrorKind::InvalidKey, 5)
+        );
+
+        let whole_tone = ScalaScale::new(
+            "whole tone".to_owned(),
+            (1..=6)
+                .map(|step| ScalaPitch::Cents(f64::from(step) * 200.0))
+                .collect(),
+        )
+        .unwrap();
+        let tuning = ScalaTuning::new(whole_tone, mapping);
+        let note = |class, octave| NotePitch::new(class, octave).to_pitch_in(&tuning);
+        // F4 is the pattern's sixth key, degree 3, and F#4 starts its repetition an octave up
+        assert!((note(NotePitchClass::F, 4).unwrap() - 432.0).abs() < 1e-9);
+        assert!((note(NotePitchClass::C, 4).unwrap() - 432.0 / 2f64.sqrt()).abs() < 1e-9);
+        assert_eq!(note(NotePitchClass::Cs, 4), None);
+        assert_eq!(note(NotePitchClass::A, 4), None);
+        assert!((note(NotePitchClass::Fs, 4).unwrap() - 432.0 * 2f64.sqrt()).abs() < 1e-9);
+        let g_flat: SpelledNotePitch = "Gb4".parse().unwrap();
+        assert_eq!(g_flat.to_pitch_in(&tuning), note(NotePitchClass::Fs, 4));
+
+        // unmapped keys are never the nearest
+        let nearest = tuning.nearest_note_pitch(440.0).unwrap();
+        assert_eq!(nearest.note_pitch(), NotePitch::new(NotePitchClass::F, 4));
+        assert!((nearest.cents() - pitch::cents_between(432.0, 440.0)).abs() < 1e-9);
+        assert_eq!(tuning.nearest_note_pitches(&[0.0]), vec![None]);
+    }
+
+    #[test]
+    fn built_in_tunings_export_to_scl() {
+        let reference = TuningReference::default();
+        for tuning in [
+            TuningSystem::EqualTempered,
+            TuningSystem::QuarterCommaMeantone(reference),
+            TuningSystem::JustIntonation(reference),
+            TuningSystem::Vallotti(reference),
+        ] {
+            let scale: ScalaScale = ScalaScale::from_tuning_system(&tuning)
+                .to_string()
+                .parse()
+                .unwrap();
+            let exported = ScalaTuning::new(scale, KeyboardMapping::default());
+            for number in 48..=72 {
+                let note_pitch = NotePitch::from_midi_number(number).unwrap();
+                let expected = note_pitch.to_pitch_using_tuning(tuning);
+                // cents are written with five decimals
+                assert!((exported.pitch(&note_pitch).unwrap() - expected).abs() < 1e-4);
+            }
+        }
+
+        let just = ScalaScale::from_tuning_system(&TuningSystem::JustIntonation(reference));
+        assert_eq!(just.pitches()[6], ScalaPitch::Ratio(3, 2));
+
+        let edo = ScalaScale::from_tuning_system(&TuningSystem::Edo {
+            divisions: NonZeroU16::new(31).unwrap(),
+            reference,
+        });
+        assert_eq!(edo.len(), 31);
+        assert_eq!(edo.description(), "31 equal divisions of the octave");
+    }
+}
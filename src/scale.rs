@@ -0,0 +1,359 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    interval::{self, SemitoneInterval},
+    pitch,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The seven rotations of the major scale.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Mode {
+    Ionian,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Aeolian,
+    Locrian,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 7] = [
+        Mode::Ionian,
+        Mode::Dorian,
+        Mode::Phrygian,
+        Mode::Lydian,
+        Mode::Mixolydian,
+        Mode::Aeolian,
+        Mode::Locrian,
+    ];
+
+    /// The degree of the major scale this mode starts on, counting the tonic as zero.
+    #[must_use]
+    pub fn rotation(self) -> usize {
+        self as usize
+    }
+
+    #[must_use]
+    pub fn pattern(self) -> ScalePattern {
+        ScalePattern::major().rotated(self.rotation())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewScalePatternError {
+    NoSteps,
+    NonPositiveStep,
+    /// The steps must add up to exactly one octave.
+    DoesNotSpanOctave,
+}
+
+impl Display for NewScalePatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NoSteps => "a scale needs at least one step",
+            Self::NonPositiveStep => "every step of a scale must ascend",
+            Self::DoesNotSpanOctave => "the steps of a scale must add up to an octave",
+        })
+    }
+}
+
+impl Error for NewScalePatternError {}
+
+/// The ordered steps between consecutive degrees of a scale, starting from its root and ending
+/// on the root an octave higher.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct ScalePattern {
+    steps: Vec<SemitoneInterval>,
+}
+
+impl ScalePattern {
+    /// # Errors
+    ///
+    /// Returns a [`NewScalePatternError`] unless every step ascends and together they span
+    /// exactly one octave.
+    pub fn new(steps: Vec<SemitoneInterval>) -> Result<Self, NewScalePatternError> {
+        if steps.is_empty() {
+            return Err(NewScalePatternError::NoSteps);
+        }
+        if steps.iter().any(|step| step.semitones() <= 0) {
+            return Err(NewScalePatternError::NonPositiveStep);
+        }
+        if steps.iter().map(SemitoneInterval::semitones).sum::<i32>() != 12 {
+            return Err(NewScalePatternError::DoesNotSpanOctave);
+        }
+        Ok(Self { steps })
+    }
+
+    fn from_semitones(steps: &[interval::Semitones]) -> Self {
+        Self {
+            steps: steps.iter().copied().map(SemitoneInterval::new).collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn major() -> Self {
+        Self::from_semitones(&[2, 2, 1, 2, 2, 2, 1])
+    }
+
+    #[must_use]
+    pub fn natural_minor() -> Self {
+        Mode::Aeolian.pattern()
+    }
+
+    #[must_use]
+    pub fn harmonic_minor() -> Self {
+        Self::from_semitones(&[2, 1, 2, 2, 1, 3, 1])
+    }
+
+    /// The ascending form of the melodic minor scale.
+    #[must_use]
+    pub fn melodic_minor() -> Self {
+        Self::from_semitones(&[2, 1, 2, 2, 2, 2, 1])
+    }
+
+    #[must_use]
+    pub fn major_pentatonic() -> Self {
+        Self::from_semitones(&[2, 2, 3, 2, 3])
+    }
+
+    #[must_use]
+    pub fn minor_pentatonic() -> Self {
+        Self::from_semitones(&[3, 2, 2, 3, 2])
+    }
+
+    /// The minor pentatonic scale with an added flattened fifth.
+    #[must_use]
+    pub fn blues() -> Self {
+        Self::from_semitones(&[3, 2, 1, 1, 3, 2])
+    }
+
+    #[must_use]
+    pub fn whole_tone() -> Self {
+        Self::from_semitones(&[2, 2, 2, 2, 2, 2])
+    }
+
+    /// The octatonic scale that starts with a half step.
+    #[must_use]
+    pub fn octatonic_half_whole() -> Self {
+        Self::from_semitones(&[1, 2, 1, 2, 1, 2, 1, 2])
+    }
+
+    /// The octatonic scale that starts with a whole step.
+    #[must_use]
+    pub fn octatonic_whole_half() -> Self {
+        Self::from_semitones(&[2, 1, 2, 1, 2, 1, 2, 1])
+    }
+
+    #[must_use]
+    pub fn chromatic() -> Self {
+        Self::from_semitones(&[1; 12])
+    }
+
+    #[must_use]
+    pub fn steps(&self) -> &[SemitoneInterval] {
+        &self.steps
+    }
+
+    /// The number of distinct degrees in the scale.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Always `false`; a scale pattern has at least one step.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The interval from the root to each degree, starting with the root itself.
+    #[must_use]
+    pub fn intervals(&self) -> Vec<SemitoneInterval> {
+        self.steps
+            .iter()
+            .scan(0, |semitones, step| {
+                let interval = SemitoneInterval::new(*semitones);
+                *semitones += step.semitones();
+                Some(interval)
+            })
+            .collect()
+    }
+
+    /// The pattern that starts on degree `rotation` (counting the root as zero) of this one.
+    #[must_use]
+    pub fn rotated(&self, rotation: usize) -> Self {
+        let mut steps = self.steps.clone();
+        steps.rotate_left(rotation % self.steps.len());
+        Self { steps }
+    }
+}
+
+/// A scale pattern built on a particular root.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scale {
+    root: pitch::NotePitchClass,
+    pattern: ScalePattern,
+}
+
+impl Scale {
+    #[must_use]
+    pub fn new(root: pitch::NotePitchClass, pattern: ScalePattern) -> Self {
+        Self { root, pattern }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> pitch::NotePitchClass {
+        self.root
+    }
+
+    #[must_use]
+    pub fn pattern(&self) -> &ScalePattern {
+        &self.pattern
+    }
+
+    /// The pitch class of every degree, starting with the root.
+    #[must_use]
+    pub fn degrees(&self) -> Vec<pitch::NotePitchClass> {
+        self.pattern
+            .intervals()
+            .iter()
+            .map(|interval| {
+                pitch::NotePitchClass::from_semitones(self.root as i32 + interval.semitones())
+            })
+            .collect()
+    }
+
+    /// The pitch class of a degree numbered from 1 for the root. Degrees past the end of the
+    /// scale wrap around into the next octave; degree 0 does not exist.
+    #[must_use]
+    pub fn degree(&self, degree: usize) -> Option<pitch::NotePitchClass> {
+        let index = degree.checked_sub(1)?;
+        Some(self.degrees()[index % self.pattern.len()])
+    }
+
+    /// The degree, numbered from 1 for the root, that `class` is in the scale.
+    #[must_use]
+    pub fn degree_of(&self, class: pitch::NotePitchClass) -> Option<usize> {
+        self.degrees()
+            .iter()
+            .position(|degree| *degree == class)
+            .map(|index| index + 1)
+    }
+
+    #[must_use]
+    pub fn contains(&self, class: pitch::NotePitchClass) -> bool {
+        self.degree_of(class).is_some()
+    }
+
+    /// Every pitch of the scale from `low` up to `high`, both included, in ascending order.
+    #[must_use]
+    pub fn note_pitches_between(
+        &self,
+        low: &pitch::NotePitch,
+        high: &pitch::NotePitch,
+    ) -> Vec<pitch::NotePitch> {
+        let degrees = self.degrees();
+        let mut note_pitches = Vec::new();
+        let mut note_pitch = *low;
+        while note_pitch <= *high {
+            if degrees.contains(&note_pitch.class()) {
+                note_pitches.push(note_pitch);
+            }
+            note_pitch = SemitoneInterval::new(1).apply_to_note_pitch(&note_pitch);
+        }
+        note_pitches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pitch::{NotePitch, NotePitchClass};
+
+    use super::*;
+
+    #[test]
+    fn built_in_patterns_span_an_octave() {
+        let patterns = [
+            ScalePattern::major(),
+            ScalePattern::natural_minor(),
+            ScalePattern::harmonic_minor(),
+            ScalePattern::melodic_minor(),
+            ScalePattern::major_pentatonic(),
+            ScalePattern::minor_pentatonic(),
+            ScalePattern::blues(),
+            ScalePattern::whole_tone(),
+            ScalePattern::octatonic_half_whole(),
+            ScalePattern::octatonic_whole_half(),
+            ScalePattern::chromatic(),
+        ];
+        for pattern in patterns {
+            assert_eq!(ScalePattern::new(pattern.steps().to_vec()), Ok(pattern));
+        }
+        assert_eq!(
+            ScalePattern::new(vec![SemitoneInterval::new(5), SemitoneInterval::new(5)]),
+            Err(NewScalePatternError::DoesNotSpanOctave)
+        );
+    }
+
+    #[test]
+    fn modes_are_rotations_of_major() {
+        let d_dorian = Scale::new(NotePitchClass::D, Mode::Dorian.pattern());
+        let c_major = Scale::new(NotePitchClass::C, ScalePattern::major());
+        let mut rotated = c_major.degrees();
+        rotated.rotate_left(1);
+        assert_eq!(d_dorian.degrees(), rotated);
+
+        let a_minor = Scale::new(NotePitchClass::A, ScalePattern::natural_minor());
+        assert_eq!(
+            a_minor.degrees(),
+            vec![
+                NotePitchClass::A,
+                NotePitchClass::B,
+                NotePitchClass::C,
+                NotePitchClass::D,
+                NotePitchClass::E,
+                NotePitchClass::F,
+                NotePitchClass::G,
+            ]
+        );
+    }
+
+    #[test]
+    fn scale_degrees_and_membership() {
+        let g_major = Scale::new(NotePitchClass::G, ScalePattern::major());
+        assert_eq!(g_major.degree(1), Some(NotePitchClass::G));
+        assert_eq!(g_major.degree(7), Some(NotePitchClass::Fs));
+        assert_eq!(g_major.degree(9), Some(NotePitchClass::A));
+        assert_eq!(g_major.degree(0), None);
+        assert_eq!(g_major.degree_of(NotePitchClass::C), Some(4));
+        assert!(g_major.contains(NotePitchClass::Fs));
+        assert!(!g_major.contains(NotePitchClass::F));
+    }
+
+    #[test]
+    fn scale_note_pitches_between() {
+        let c_pentatonic = Scale::new(NotePitchClass::C, ScalePattern::major_pentatonic());
+        let note_pitches = c_pentatonic.note_pitches_between(
+            &NotePitch::new(NotePitchClass::G, 3),
+            &NotePitch::new(NotePitchClass::D, 4),
+        );
+        assert_eq!(
+            note_pitches,
+            vec![
+                NotePitch::new(NotePitchClass::G, 3),
+                NotePitch::new(NotePitchClass::A, 3),
+                NotePitch::new(NotePitchClass::C, 4),
+                NotePitch::new(NotePitchClass::D, 4),
+            ]
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use std::sync::OnceLock;
+
+use crate::{
+    composition::Timeline,
+    rhythm::{self, Duration},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a tempo ramp moves between its tempi over score time.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RampCurve {
+    /// The tempo changes by the same number of beats per minute in every beat.
+    Linear,
+    /// The tempo changes by the same factor in every beat.
+    Exponential,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TempoEvent {
+    /// Jumps to a new tempo, possibly counted in a different beat.
+    Change(rhythm::Rhythm),
+    /// Moves from the tempo in effect to `to` over `length`, counted in the current beat. A
+    /// later event inside the ramp cuts it short, and a ramp of zero length is an instant change.
+    Ramp {
+        to: rhythm::Tempo,
+        length: Duration,
+        curve: RampCurve,
+    },
+    /// Holds the music still for `seconds` before continuing. A hold that is negative or not
+    /// finite counts as no hold.
+    Fermata { seconds: f64 },
+}
+
+/// A stretch of score time over which the tempo is constant or follows a single ramp.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    /// Whole notes from the start of the score.
+    start: f64,
+    /// Whole notes, infinite for the last segment.
+    length: f64,
+    start_seconds: f64,
+    /// The length of the beat in whole notes.
+    beat: f64,
+    from_bpm: f64,
+    to_bpm: f64,
+    curve: Option<RampCurve>,
+}
+
+impl Segment {
+    fn is_constant(&self) -> bool {
+        self.curve.is_none()
+            || (self.from_bpm - self.to_bpm).abs() < f64::EPSILON
+            || self.length.is_infinite()
+    }
+
+    /// Beats per minute gained per whole note for a linear ramp, or the logarithm of the factor
+    /// gained per whole note for an exponential ramp.
+    fn rate(&self) -> f64 {
+        match self.curve {
+            Some(RampCurve::Exponential) => (self.to_bpm / self.from_bpm).ln() / self.length,
+            _ => (self.to_bpm - self.from_bpm) / self.length,
+        }
+    }
+
+    fn bpm_at(&self, whole_notes: f64) -> f64 {
+        if self.is_constant() {
+            return self.from_bpm;
+        }
+        match self.curve {
+            Some(RampCurve::Exponential) => self.from_bpm * (self.rate() * whole_notes).exp(),
+            _ => self.from_bpm + self.rate() * whole_notes,
+        }
+    }
+
+    /// The seconds taken by the first `whole_notes` of the segment.
+    fn seconds(&self, whole_notes: f64) -> f64 {
+        let seconds_per_minute_beat = 60.0 / self.beat;
+        if self.is_constant() {
+            return seconds_per_minute_beat * whole_notes / self.from_bpm;
+        }
+        let rate = self.rate();
+        match self.curve {
+            Some(RampCurve::Exponential) => {
+                seconds_per_minute_beat * (1.0 - (-rate * whole_notes).exp())
+                    / (self.from_bpm * rate)
+            }
+            _ => seconds_per_minute_beat * (self.bpm_at(whole_notes) / self.from_bpm).ln() / rate,
+        }
+    }
+
+    /// The whole notes into the segment after `seconds`; the inverse of [`Segment::seconds`].
+    fn whole_notes(&self, seconds: f64) -> f64 {
+        let beats_per_minute_second = self.beat / 60.0;
+        if self.is_constant() {
+            return seconds * beats_per_minute_second * self.from_bpm;
+        }
+        let rate = self.rate();
+        match self.curve {
+            Some(RampCurve::Exponential) => {
+                -(1.0 - seconds * beats_per_minute_second * self.from_bpm * rate).ln() / rate
+            }
+            _ => self.from_bpm * ((seconds * beats_per_minute_second * rate).exp() - 1.0) / rate,
+        }
+    }
+
+    fn end_seconds(&self) -> f64 {
+        self.start_seconds + self.seconds(self.length)
+    }
+}
+
+/// A ramp in progress while segments are being built.
+#[derive(Clone, Copy)]
+struct ActiveRamp {
+    start: f64,
+    end: f64,
+    from_bpm: f64,
+    to_bpm: f64,
+    curve: RampCurve,
+}
+
+impl ActiveRamp {
+    fn bpm_at(&self, whole_notes: f64) -> f64 {
+        Segment {
+            start: self.start,
+            length: self.end - self.start,
+            start_seconds: 0.0,
+            beat: 1.0,
+            from_bpm: self.from_bpm,
+            to_bpm: self.to_bpm,
+            curve: Some(self.curve),
+        }
+        .bpm_at(whole_notes - self.start)
+    }
+}
+
+/// The tempo of a piece at every score offset, for converting between offsets and seconds.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TempoMap {
+    initial: rhythm::Rhythm,
+    events: Timeline<TempoEvent>,
+    /// Built from the events on first use and cleared whenever they change.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    segments: OnceLock<Vec<Segment>>,
+}
+
+impl PartialEq for TempoMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.initial == other.initial && self.events == other.events
+    }
+}
+
+impl TempoMap {
+    #[must_use]
+    pub fn new(initial: rhythm::Rhythm) -> Self {
+        Self {
+            initial,
+            events: Timeline::new(),
+            segments: OnceLock::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn initial(&self) -> &rhythm::Rhythm {
+        &self.initial
+    }
+
+    #[must_use]
+    pub fn events(&self) -> &Timeline<TempoEvent> {
+        &self.events
+    }
+
+    pub fn insert(&mut self, offset: Duration, event: TempoEvent) {
+        self.events.insert(offset, event);
+        self.segments = OnceLock::new();
+    }
+
+    pub fn change(&mut self, offset: Duration, rhythm: rhythm::Rhythm) {
+        self.insert(offset, TempoEvent::Change(rhythm));
+    }
+
+    pub fn ramp(
+        &mut self,
+        offset: Duration,
+        to: rhythm::Tempo,
+        length: Duration,
+        curve: RampCurve,
+    ) {
+        self.insert(offset, TempoEvent::Ramp { to, length, curve });
+    }
+
+    /// Holds the music still at `offset` for `seconds`, which count as zero if negative or not
+    /// finite.
+    pub fn fermata(&mut self, offset: Duration, seconds: f64) {
+        let seconds = if seconds.is_finite() {
+            seconds.max(0.0)
+        } else {
+            0.0
+        };
+        self.insert(offset, TempoEvent::Fermata { seconds });
+    }
+
+    fn segments(&self) -> &[Segment] {
+        self.segments.get_or_init(|| self.build_segments())
+    }
+
+    /// Splits the score into segments, the last of which never ends.
+    fn build_segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut position = 0.0;
+        let mut seconds = 0.0;
+        let mut beat = self
+            .initial
+            .beat_assignment()
+            .beat_duration()
+            .ratio()
+            .to_f64();
+        let mut bpm = self.initial.tempo().bpm();
+        let mut ramp: Option<ActiveRamp> = None;
+
+        let mut advance = |to: f64,
+                           position: &mut f64,
+                           seconds: &mut f64,
+                           bpm: &mut f64,
+                           beat: f64,
+                           ramp: &mut Option<ActiveRamp>| {
+            while *position < to {
+                let (end, to_bpm, curve) = match ramp {
+                    Some(active) if active.end <= to => {
+                        (active.end, active.to_bpm, Some(active.curve))
+                    }
+                    Some(active) => (to, active.bpm_at(to), Some(active.curve)),
+                    None => (to, *bpm, None),
+                };
+                let segment = Segment {
+                    start: *position,
+                    length: end - *position,
+                    start_seconds: *seconds,
+                    beat,
+                    from_bpm: *bpm,
+                    to_bpm,
+                    curve,
+                };
+                *seconds = segment.end_seconds();
+                segments.push(segment);
+                *position = end;
+                *bpm = to_bpm;
+                if ramp.is_some_and(|active| active.end <= *position) {
+                    *ramp = None;
+                }
+            }
+        };
+
+        for (offset, event) in self.events.iter() {
+            let offset = offset.ratio().to_f64();
+            advance(
+                offset,
+                &mut position,
+                &mut seconds,
+                &mut bpm,
+                beat,
+                &mut ramp,
+            );
+            match event {
+                TempoEvent::Change(rhythm) => {
+                    bpm = rhythm.tempo().bpm();
+                    beat = rhythm.beat_assignment().beat_duration().ratio().to_f64();
+                    ramp = None;
+                }
+                // a ramp over no time has no curve to follow
+                TempoEvent::Ramp { to, length, .. } if length.is_zero() => {
+                    bpm = to.bpm();
+                    ramp = None;
+                }
+                TempoEvent::Ramp { to, length, curve } => {
+                    ramp = Some(ActiveRamp {
+                        start: offset,
+                        end: offset + length.ratio().to_f64(),
+                        from_bpm: bpm,
+                        to_bpm: to.bpm(),
+                        curve: *curve,
+                    });
+                }
+                TempoEvent::Fermata { seconds: hold } if hold.is_finite() && *hold > 0.0 => {
+                    seconds += hold;
+                }
+                TempoEvent::Fermata { .. } => {}
+            }
+        }
+        if let Some(active) = ramp {
+            advance(
+                active.end,
+                &mut position,
+                &mut seconds,
+                &mut bpm,
+                beat,
+                &mut ramp,
+            );
+        }
+        segments.push(Segment {
+            start: position,
+            length: f64::INFINITY,
+            start_seconds: seconds,
+            beat,
+            from_bpm: bpm,
+            to_bpm: bpm,
+            curve: None,
+        });
+        segments
+    }
+
+    /// The segment that `whole_notes` falls in, preferring the one that ends there over the one
+    /// that starts there so that a fermata's hold comes after its offset.
+    fn segment_at(segments: &[Segment], whole_notes: f64) -> &Segment {
+        let index = segments.partition_point(|segment| segment.start < whole_notes);
+        &segments[index.saturating_sub(1)]
+    }
+
+    /// The tempo at `offset`, counted in the beat in effect there.
+    #[must_use]
+    pub fn tempo_at(&self, offset: Duration) -> rhythm::Tempo {
+        let whole_notes = offset.ratio().to_f64();
+        let segments = self.segments();
+        let index = segments.partition_point(|segment| segment.start <= whole_notes);
+        let segment = &segments[index.saturating_sub(1)];
+        rhythm::Tempo::new(segment.bpm_at(whole_notes - segment.start))
+            .unwrap_or_else(|| unreachable!("tempi stay between two valid tempi"))
+    }
+
+    /// The seconds from the start of the score until `offset` is reached. A fermata at `offset`
+    /// is not included; it delays everything after it.
+    #[must_use]
+    pub fn seconds_at(&self, offset: Duration) -> f64 {
+        if offset == Duration::zero() {
+            return 0.0;
+        }
+        let whole_notes = offset.ratio().to_f64();
+        let segments = self.segments();
+        let segment = Self::segment_at(segments, whole_notes);
+        segment.start_seconds + segment.seconds(whole_notes - segment.start)
+    }
+
+    /// The score position, in whole notes, reached after `seconds`. During a fermata this is
+    /// the fermata's offset.
+    #[must_use]
+    pub fn position_at(&self, seconds: f64) -> f64 {
+        let segments = self.segments();
+        let index = segments.partition_point(|segment| segment.start_seconds <= seconds);
+        let Some(segment) = index.checked_sub(1).map(|index| &segments[index]) else {
+            return 0.0;
+        };
+        let into = (seconds - segment.start_seconds).min(segment.seconds(segment.length));
+        segment.start + segment.whole_notes(into).min(segment.length)
+    }
+
+    /// [`TempoMap::position_at`] rounded to the nearest multiple of `resolution`.
+    #[must_use]
+    pub fn offset_at(&self, seconds: f64, resolution: Duration) -> Duration {
+        let steps = (self.position_at(seconds) / resolution.ratio().to_f64()).round();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = steps.clamp(0.0, f64::from(u32::MAX)) as u32;
+        resolution * steps
+    }
+}
+
+impl From<rhythm::Rhythm> for TempoMap {
+    fn from(initial: rhythm::Rhythm) -> Self {
+        Self::new(initial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rhythm::{BeatAssignment, Rhythm, Tempo};
+
+    use super::*;
+
+    fn quarter_notes_at(bpm: f64) -> Rhythm {
+        Rhythm::new(
+            Tempo::new(bpm).unwrap(),
+            BeatAssignment::new(Duration::new(1, 4).unwrap()),
+        )
+    }
+
+    fn whole_notes(count: u32) -> Duration {
+        Duration::new(count, 1).unwrap()
+    }
+
+    #[test]
+    fn instant_changes_and_fermatas() {
+        let mut tempo_map = TempoMap::new(quarter_notes_at(120.0));
+        tempo_map.change(whole_notes(1), quarter_notes_at(60.0));
+        tempo_map.fermata(whole_notes(2), 1.5);
+        // the same speed, counted in half notes
+        tempo_map.change(
+            whole_notes(3),
+            Rhythm::new(
+                Tempo::new(30.0).unwrap(),
+                BeatAssignment::new(Duration::new(1, 2).unwrap()),
+            ),
+        );
+
+        assert!((tempo_map.seconds_at(Duration::new(1, 2).unwrap()) - 1.0).abs() < 1e-9);
+        assert!((tempo_map.seconds_at(whole_notes(1)) - 2.0).abs() < 1e-9);
+        assert!((tempo_map.seconds_at(whole_notes(2)) - 6.0).abs() < 1e-9);
+        assert!((tempo_map.seconds_at(whole_notes(3)) - 11.5).abs() < 1e-9);
+        assert!((tempo_map.seconds_at(whole_notes(4)) - 15.5).abs() < 1e-9);
+
+        assert!((tempo_map.position_at(7.0) - 2.0).abs() < 1e-9);
+        assert!((tempo_map.position_at(8.5) - 2.25).abs() < 1e-9);
+        assert_eq!(
+            tempo_map.offset_at(9.5, Duration::new(1, 16).unwrap()),
+            Duration::new(5, 2).unwrap()
+        );
+        assert!((tempo_map.tempo_at(whole_notes(1)).bpm() - 60.0).abs() < 1e-9);
+        assert!((tempo_map.tempo_at(Duration::new(1, 2).unwrap()).bpm() - 120.0).abs() < 1e-9);
+
+        // holds that are negative or not finite take no time
+        tempo_map.fermata(whole_notes(4), -3.0);
+        tempo_map.insert(whole_notes(4), TempoEvent::Fermata { seconds: f64::NAN });
+        assert!((tempo_map.seconds_at(whole_notes(5)) - 19.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ramps() {
+        let mut linear = TempoMap::new(quarter_notes_at(60.0));
+        linear.ramp(
+            Duration::zero(),
+            Tempo::new(120.0).unwrap(),
+            whole_notes(1),
+            RampCurve::Linear,
+        );
+        assert!((linear.seconds_at(whole_notes(1)) - 4.0 * 2f64.ln()).abs() < 1e-9);
+        assert!((linear.tempo_at(Duration::new(1, 2).unwrap()).bpm() - 90.0).abs() < 1e-9);
+        // after the ramp the new tempo holds
+        assert!(
+            ((linear.seconds_at(whole_notes(2)) - linear.seconds_at(whole_notes(1))) - 2.0).abs()
+                < 1e-9
+        );
+
+        let mut exponential = TempoMap::new(quarter_notes_at(60.0));
+        exponential.ramp(
+            Duration::zero(),
+            Tempo::new(120.0).unwrap(),
+            whole_notes(1),
+            RampCurve::Exponential,
+        );
+        assert!((exponential.seconds_at(whole_notes(1)) - 2.0 / 2f64.ln()).abs() < 1e-9);
+        assert!(
+            (exponential.tempo_at(Duration::new(1, 2).unwrap()).bpm() - 60.0 * 2f64.sqrt()).abs()
+                < 1e-9
+        );
+
+        // a change in the middle of a ramp cuts it short
+        linear.change(Duration::new(1, 2).unwrap(), quarter_notes_at(60.0));
+        assert!((linear.seconds_at(Duration::new(1, 2).unwrap()) - 4.0 * 1.5f64.ln()).abs() < 1e-9);
+        assert!((linear.tempo_at(whole_notes(1)).bpm() - 60.0).abs() < 1e-9);
+
+        // a ramp over no time jumps straight to its tempo
+        let mut sudden = TempoMap::new(quarter_notes_at(60.0));
+        sudden.ramp(
+            whole_notes(1),
+            Tempo::new(120.0).unwrap(),
+            Duration::zero(),
+            RampCurve::Linear,
+        );
+        sudden.change(whole_notes(2), quarter_notes_at(60.0));
+        assert!((sudden.seconds_at(Duration::new(3, 2).unwrap()) - 5.0).abs() < 1e-9);
+        assert!((sudden.seconds_at(whole_notes(3)) - 10.0).abs() < 1e-9);
+        assert!((sudden.position_at(5.0) - 1.5).abs() < 1e-9);
+
+        for tempo_map in [linear, exponential] {
+            for sixteenths in 0..48 {
+                let offset = Duration::new(1, 16).unwrap() * sixteenths;
+                let seconds = tempo_map.seconds_at(offset);
+                assert!((tempo_map.position_at(seconds) - offset.ratio().to_f64()).abs() < 1e-9);
+                assert_eq!(
+                    tempo_map.offset_at(seconds, Duration::new(1, 16).unwrap()),
+                    offset
+                );
+            }
+        }
+    }
+}
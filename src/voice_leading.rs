@@ -0,0 +1,464 @@
+use crate::{
+    harmony,
+    interval::{SemitoneInterval, Semitones},
+    key, pitch,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The move of one voice from a pitch of one chord to a pitch of the next.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VoiceMotion {
+    from: pitch::NotePitch,
+    to: pitch::NotePitch,
+}
+
+impl VoiceMotion {
+    #[must_use]
+    pub fn new(from: pitch::NotePitch, to: pitch::NotePitch) -> Self {
+        Self { from, to }
+    }
+
+    #[must_use]
+    pub fn from(&self) -> pitch::NotePitch {
+        self.from
+    }
+
+    #[must_use]
+    pub fn to(&self) -> pitch::NotePitch {
+        self.to
+    }
+
+    /// The signed size of the move, positive going up.
+    #[must_use]
+    pub fn semitones(&self) -> Semitones {
+        SemitoneInterval::new_from_note_pitches(&self.from, &self.to).semitones()
+    }
+
+    #[must_use]
+    pub fn is_static(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+/// A classical voice leading error.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FaultKind {
+    /// Two voices a fifth apart both move to another fifth, in parallel or contrary motion.
+    ParallelFifths,
+    /// Two voices an octave or unison apart both move to another octave or unison.
+    ParallelOctaves,
+    /// The outer voices approach a fifth in similar motion with a leap in the upper voice.
+    HiddenFifths,
+    /// The outer voices approach an octave in similar motion with a leap in the upper voice.
+    HiddenOctaves,
+    /// A lower voice ends above a higher one.
+    VoiceCrossing,
+    /// A voice moves past the pitch its neighbour just left.
+    VoiceOverlap,
+    /// Two adjacent upper voices end more than an octave apart.
+    Spacing,
+    /// The leading tone in an outer voice does not rise to the tonic.
+    UnresolvedLeadingTone,
+    /// The seventh of a seventh chord does not fall by step.
+    UnresolvedSeventh,
+}
+
+/// A voice leading error together with the voices involved, numbered from the bass up.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fault {
+    kind: FaultKind,
+    voices: Vec<usize>,
+    motions: Vec<VoiceMotion>,
+}
+
+impl Fault {
+    fn new(kind: FaultKind, voices: Vec<usize>, motions: &[VoiceMotion]) -> Self {
+        let motions = voices.iter().map(|voice| motions[*voice]).collect();
+        Self {
+            kind,
+            voices,
+            motions,
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> FaultKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn voices(&self) -> &[usize] {
+        &self.voices
+    }
+
+    /// The pitches each of the voices moves between, in the order of [`Fault::voices`].
+    #[must_use]
+    pub fn motions(&self) -> &[VoiceMotion] {
+        &self.motions
+    }
+}
+
+/// The voice leading from one chord to the next and its errors.
+///
+/// Crossing and spacing are checked in the chord moved to, so that checking each step of a
+/// progression reports every chord after the first once.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VoiceLeading {
+    motions: Vec<VoiceMotion>,
+    faults: Vec<Fault>,
+}
+
+impl VoiceLeading {
+    /// Checks the motions of voices given from the bass up. The leading tone is only checked
+    /// when a `key` is given and the first chord is rooted on its dominant or leading tone.
+    #[must_use]
+    pub fn new(motions: Vec<VoiceMotion>, key: Option<&key::Key>) -> Self {
+        let faults = find_faults(&motions, key);
+        Self { motions, faults }
+    }
+
+    /// Leads the pitches of `from` to those of `to` with the least total motion, without
+    /// crossing voices. When the chords differ in size, pitches of the smaller one are doubled.
+    #[must_use]
+    pub fn between(from: &harmony::Chord, to: &harmony::Chord, key: Option<&key::Key>) -> Self {
+        let from: Vec<_> = from.note_pitches().iter().copied().collect();
+        let to: Vec<_> = to.note_pitches().iter().copied().collect();
+        let motions = if from.len() >= to.len() {
+            minimal_motions(&from, &to)
+                .into_iter()
+                .map(|(from, to)| VoiceMotion::new(from, to))
+                .collect()
+        } else {
+            minimal_motions(&to, &from)
+                .into_iter()
+                .map(|(to, from)| VoiceMotion::new(from, to))
+                .collect()
+        };
+        Self::new(motions, key)
+    }
+
+    /// The voice leading of each step of a progression.
+    #[must_use]
+    pub fn progression(chords: &[harmony::Chord], key: Option<&key::Key>) -> Vec<Self> {
+        chords
+            .windows(2)
+            .map(|pair| Self::between(&pair[0], &pair[1], key))
+            .collect()
+    }
+
+    /// The motion of each voice, from the bass up.
+    #[must_use]
+    pub fn motions(&self) -> &[VoiceMotion] {
+        &self.motions
+    }
+
+    #[must_use]
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    #[must_use]
+    pub fn has_fault(&self, kind: FaultKind) -> bool {
+        self.faults.iter().any(|fault| fault.kind == kind)
+    }
+
+    /// The number of semitones moved by all voices together.
+    #[must_use]
+    pub fn displacement(&self) -> u32 {
+        self.motions
+            .iter()
+            .map(|motion| motion.semitones().unsigned_abs())
+            .sum()
+    }
+}
+
+/// Pairs every pitch of `larger` with one of `smaller`, both sorted, so that every pitch of
+/// `smaller` is used, no pairs cross and the total distance is least.
+fn minimal_motions(
+    larger: &[pitch::NotePitch],
+    smaller: &[pitch::NotePitch],
+) -> Vec<(pitch::NotePitch, pitch::NotePitch)> {
+    if smaller.is_empty() {
+        return Vec::new();
+    }
+    let distance =
+        |i: usize, j: usize| (larger[i].semitones() - smaller[j].semitones()).unsigned_abs();
+    // costs[i][j]: least distance of pairing larger[..=i] with smaller[..=j], larger[i] with
+    // smaller[j]
+    let mut costs = vec![vec![u32::MAX; smaller.len()]; larger.len()];
+    for i in 0..larger.len() {
+        for j in 0..smaller.len().min(i + 1) {
+            let previous = if i == 0 {
+                0
+            } else if j == 0 {
+                costs[i - 1][0]
+            } else {
+                costs[i - 1][j].min(costs[i - 1][j - 1])
+            };
+            if previous != u32::MAX {
+                costs[i][j] = previous + distance(i, j);
+            }
+        }
+    }
+
+    let mut pairs = Vec::with_capacity(larger.len());
+    let mut j = smaller.len() - 1;
+    for i in (0..larger.len()).rev() {
+        pairs.push((larger[i], smaller[j]));
+        if i > 0 && j > 0 && costs[i - 1][j - 1] < costs[i - 1][j] {
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+fn find_faults(motions: &[VoiceMotion], key: Option<&key::Key>) -> Vec<Fault> {
+    let mut faults = Vec::new();
+    let top = motions.len().saturating_sub(1);
+
+    for lower in 0..motions.len() {
+        for upper in lower + 1..motions.len() {
+            let (low, high) = (motions[lower], motions[upper]);
+            let before = high.from.semitones() - low.from.semitones();
+            let after = high.to.semitones() - low.to.semitones();
+            let voices = vec![lower, upper];
+
+            if after < 0 {
+                faults.push(Fault::new(
+                    FaultKind::VoiceCrossing,
+                    voices.clone(),
+                    motions,
+                ));
+            }
+            if low.to.semitones() > high.from.semitones()
+                || high.to.semitones() < low.from.semitones()
+            {
+                faults.push(Fault::new(FaultKind::VoiceOverlap, voices.clone(), motions));
+            }
+            if upper == lower + 1 && lower > 0 && after > 12 {
+                faults.push(Fault::new(FaultKind::Spacing, voices.clone(), motions));
+            }
+
+            let perfect = |interval: Semitones| match interval.rem_euclid(12) {
+                0 => Some(FaultKind::ParallelOctaves),
+                7 => Some(FaultKind::ParallelFifths),
+                _ => None,
+            };
+            let (Some(kind), false) = (perfect(after), after < 0) else {
+                continue;
+            };
+            if low.is_static() || high.is_static() {
+                continue;
+            }
+            if perfect(before) == Some(kind) && before >= 0 {
+                faults.push(Fault::new(kind, voices, motions));
+            } else if lower == 0
+                && upper == top
+                && low.semitones().signum() == high.semitones().signum()
+                && high.semitones().abs() > 2
+            {
+                let kind = if kind == FaultKind::ParallelFifths {
+                    FaultKind::HiddenFifths
+                } else {
+                    FaultKind::HiddenOctaves
+                };
+                faults.push(Fault::new(kind, voices, motions));
+            }
+        }
+    }
+
+    if let (Some(key), false) = (key, motions.is_empty()) {
+        let tonic = key.tonic().note_pitch_class() as i32;
+        // the leading tone only needs to rise out of a dominant or leading-tone chord
+        let from = harmony::Chord::new(motions.iter().map(VoiceMotion::from).collect());
+        let dominant = from
+            .identify()
+            .into_iter()
+            .next()
+            .is_some_and(|identification| {
+                matches!(
+                    (identification.root() as i32 - tonic).rem_euclid(12),
+                    7 | 11
+                )
+            });
+        let leading_tone = pitch::NotePitchClass::from_semitones(tonic - 1);
+        let mut outer_voices = vec![0, top];
+        outer_voices.dedup();
+        for voice in outer_voices {
+            let motion = motions[voice];
+            if dominant
+                && motion.from.class() == leading_tone
+                && motion.to.class() != leading_tone
+                && motion.semitones() != 1
+            {
+                faults.push(Fault::new(
+                    FaultKind::UnresolvedLeadingTone,
+                    vec![voice],
+                    motions,
+                ));
+            }
+        }
+    }
+
+    faults.extend(unresolved_sevenths(motions));
+    faults
+}
+
+fn unresolved_sevenths(motions: &[VoiceMotion]) -> Vec<Fault> {
+    let chord = |pitches: &mut dyn Iterator<Item = pitch::NotePitch>| {
+        harmony::Chord::new(pitches.collect())
+    };
+    let from = chord(&mut motions.iter().map(VoiceMotion::from));
+    let to = chord(&mut motions.iter().map(VoiceMotion::to));
+    let Some(identification) = from.identify().into_iter().next() else {
+        return Vec::new();
+    };
+    if to.chord_class() == from.chord_class() {
+        return Vec::new();
+    }
+    let quality = identification.quality();
+    let seventh = if quality == harmony::ChordQuality::Diminished7 {
+        Some(9)
+    } else {
+        quality
+            .full_semitones()
+            .into_iter()
+            .find(|semitones| *semitones == 10 || *semitones == 11)
+    };
+    let Some(seventh) = seventh else {
+        return Vec::new();
+    };
+    let seventh = pitch::NotePitchClass::from_semitones(identification.root() as i32 + seventh);
+
+    motions
+        .iter()
+        .enumerate()
+        .filter(|(_, motion)| {
+            motion.from.class() == seventh && !matches!(motion.semitones(), -2 | -1)
+        })
+        .map(|(voice, _)| Fault::new(FaultKind::UnresolvedSeventh, vec![voice], motions))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::pitch::{NotePitch, NotePitchClass, SpelledNotePitchClass};
+
+    use super::*;
+
+    fn pitches(names: &[(NotePitchClass, i32)]) -> Vec<NotePitch> {
+        names
+            .iter()
+            .map(|(class, octave)| NotePitch::new(*class, *octave))
+            .collect()
+    }
+
+    fn leading(from: &[(NotePitchClass, i32)], to: &[(NotePitchClass, i32)]) -> VoiceLeading {
+        let key = key::Key::major(SpelledNotePitchClass::from_str("C").unwrap()).unwrap();
+        let motions = pitches(from)
+            .into_iter()
+            .zip(pitches(to))
+            .map(|(from, to)| VoiceMotion::new(from, to))
+            .collect();
+        VoiceLeading::new(motions, Some(&key))
+    }
+
+    fn kinds(voice_leading: &VoiceLeading) -> Vec<FaultKind> {
+        voice_leading.faults().iter().map(Fault::kind).collect()
+    }
+
+    #[test]
+    fn minimal_motion() {
+        use NotePitchClass::{B, C, D, E, F, G};
+        let c_major = harmony::Chord::new(pitches(&[(C, 4), (E, 4), (G, 4)]).into_iter().collect());
+        let g_major = harmony::Chord::new(pitches(&[(B, 3), (D, 4), (G, 4)]).into_iter().collect());
+        let voice_leading = VoiceLeading::between(&c_major, &g_major, None);
+        assert_eq!(voice_leading.displacement(), 3);
+        assert!(voice_leading.faults().is_empty());
+
+        let g_seventh = harmony::Chord::new(
+            pitches(&[(B, 3), (D, 4), (F, 4), (G, 4)])
+                .into_iter()
+                .collect(),
+        );
+        let voice_leading = VoiceLeading::between(&c_major, &g_seventh, None);
+        assert_eq!(voice_leading.displacement(), 4);
+        assert_eq!(
+            voice_leading.motions(),
+            &[
+                VoiceMotion::new(NotePitch::new(C, 4), NotePitch::new(B, 3)),
+                VoiceMotion::new(NotePitch::new(E, 4), NotePitch::new(D, 4)),
+                VoiceMotion::new(NotePitch::new(E, 4), NotePitch::new(F, 4)),
+                VoiceMotion::new(NotePitch::new(G, 4), NotePitch::new(G, 4)),
+            ]
+        );
+
+        let progression = VoiceLeading::progression(&[c_major, g_seventh.clone(), g_seventh], None);
+        assert_eq!(progression.len(), 2);
+        assert_eq!(progression[1].displacement(), 0);
+    }
+
+    #[test]
+    fn classical_errors() {
+        use NotePitchClass::{A, B, C, D, E, F, G};
+        let parallels = leading(
+            &[(C, 3), (G, 3), (E, 4), (C, 5)],
+            &[(D, 3), (A, 3), (F, 4), (D, 5)],
+        );
+        assert_eq!(
+            kinds(&parallels),
+            vec![FaultKind::ParallelFifths, FaultKind::ParallelOctaves]
+        );
+        assert_eq!(
+            parallels.faults()[0].motions(),
+            &[
+                VoiceMotion::new(NotePitch::new(C, 3), NotePitch::new(D, 3)),
+                VoiceMotion::new(NotePitch::new(G, 3), NotePitch::new(A, 3)),
+            ]
+        );
+
+        let hidden = leading(&[(C, 3), (E, 4)], &[(F, 3), (C, 5)]);
+        assert_eq!(kinds(&hidden), vec![FaultKind::HiddenFifths]);
+
+        let crossing = leading(&[(C, 3), (C, 4), (E, 4)], &[(C, 3), (G, 4), (F, 4)]);
+        assert_eq!(
+            kinds(&crossing),
+            vec![FaultKind::VoiceCrossing, FaultKind::VoiceOverlap]
+        );
+
+        let spacing = leading(&[(C, 3), (C, 4), (E, 4)], &[(C, 3), (C, 4), (G, 5)]);
+        assert_eq!(kinds(&spacing), vec![FaultKind::Spacing]);
+
+        let resolved = leading(
+            &[(G, 2), (F, 4), (B, 4), (D, 5)],
+            &[(C, 3), (E, 4), (C, 5), (C, 5)],
+        );
+        assert!(resolved.faults().is_empty());
+        let unresolved = leading(
+            &[(G, 2), (F, 4), (B, 4), (D, 5)],
+            &[(C, 3), (G, 4), (C, 5), (E, 5)],
+        );
+        assert_eq!(kinds(&unresolved), vec![FaultKind::UnresolvedSeventh]);
+        assert_eq!(unresolved.faults()[0].voices(), &[1]);
+        let leading_tone = leading(
+            &[(G, 2), (D, 4), (G, 4), (B, 4)],
+            &[(C, 3), (E, 4), (G, 4), (G, 4)],
+        );
+        assert_eq!(kinds(&leading_tone), vec![FaultKind::UnresolvedLeadingTone]);
+        // in iii to vi the leading tone is only the fifth of the chord and is free to fall
+        let mediant = leading(
+            &[(E, 3), (G, 3), (E, 4), (B, 4)],
+            &[(A, 2), (A, 3), (E, 4), (A, 4)],
+        );
+        assert!(mediant.faults().is_empty());
+    }
+}
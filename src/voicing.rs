@@ -0,0 +1,376 @@
+use std::collections::HashSet;
+
+use crate::{
+    harmony,
+    interval::Semitones,
+    key, pitch,
+    voice_leading::{FaultKind, VoiceLeading, VoiceMotion},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One of the four parts of a chorale texture, from the bottom up.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Voice {
+    Bass,
+    Tenor,
+    Alto,
+    Soprano,
+}
+
+impl Voice {
+    pub const ALL: [Voice; 4] = [Voice::Bass, Voice::Tenor, Voice::Alto, Voice::Soprano];
+
+    /// The usual lowest and highest pitch of the voice: E2–C4 for the bass, C3–G4 for the
+    /// tenor, G3–C5 for the alto and C4–G5 for the soprano.
+    #[must_use]
+    pub fn range(self) -> (pitch::NotePitch, pitch::NotePitch) {
+        use pitch::NotePitchClass::{C, E, G};
+        let (low, high) = match self {
+            Self::Bass => ((E, 2), (C, 4)),
+            Self::Tenor => ((C, 3), (G, 4)),
+            Self::Alto => ((G, 3), (C, 5)),
+            Self::Soprano => ((C, 4), (G, 5)),
+        };
+        (
+            pitch::NotePitch::new(low.0, low.1),
+            pitch::NotePitch::new(high.0, high.1),
+        )
+    }
+}
+
+/// A four-part setting of a progression and how far it strays from the rules.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Voicing {
+    voices: Vec<[pitch::NotePitch; 4]>,
+    cost: u32,
+}
+
+impl Voicing {
+    /// The pitches of each chord in the order of [`Voice::ALL`].
+    #[must_use]
+    pub fn voices(&self) -> &[[pitch::NotePitch; 4]] {
+        &self.voices
+    }
+
+    #[must_use]
+    pub fn chords(&self) -> Vec<harmony::Chord> {
+        self.voices
+            .iter()
+            .map(|voices| harmony::Chord::new(voices.iter().copied().collect()))
+            .collect()
+    }
+
+    /// Lower is better; a voicing breaking no rule with smooth voice leading costs only the
+    /// semitones its voices move.
+    #[must_use]
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+/// Sets progressions in four parts within the ranges of the voices.
+///
+/// Voicings are searched for the fewest broken rules: parallel and hidden perfect intervals,
+/// overlapping voices, unresolved leading tones and sevenths, doubled leading tones and
+/// sevenths, missing chord tones and inversions all add to the cost of a voicing, as does every
+/// semitone moved. Voices never cross, share a pitch or lie more than an octave apart above the
+/// bass.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FourPartVoicer {
+    key: key::Key,
+    ranges: [(pitch::NotePitch, pitch::NotePitch); 4],
+}
+
+impl FourPartVoicer {
+    #[must_use]
+    pub fn new(key: key::Key) -> Self {
+        Self {
+            key,
+            ranges: Voice::ALL.map(Voice::range),
+        }
+    }
+
+    #[must_use]
+    pub fn with_range(
+        mut self,
+        voice: Voice,
+        low: pitch::NotePitch,
+        high: pitch::NotePitch,
+    ) -> Self {
+        self.ranges[voice as usize] = (low, high);
+        self
+    }
+
+    #[must_use]
+    pub fn key(&self) -> key::Key {
+        self.key
+    }
+
+    #[must_use]
+    pub fn range(&self, voice: Voice) -> (pitch::NotePitch, pitch::NotePitch) {
+        self.ranges[voice as usize]
+    }
+
+    /// Up to `count` of the best voicings of `progression`, best first. Returns nothing when a
+    /// chord cannot be voiced within the ranges.
+    #[must_use]
+    pub fn voice(&self, progression: &[harmony::RootedChordClass], count: usize) -> Vec<Voicing> {
+        if progression.is_empty() || count == 0 {
+            return Vec::new();
+        }
+        let candidates: Vec<Vec<([pitch::NotePitch; 4], u32)>> = progression
+            .iter()
+            .map(|chord| self.candidates(chord))
+            .collect();
+
+        // for every candidate of a chord, the best paths reaching it as (cost, previous
+        // candidate, rank of the previous path)
+        let mut paths: Vec<Vec<Vec<(u32, usize, usize)>>> = vec![candidates[0]
+            .iter()
+            .map(|(_, cost)| vec![(*cost, 0, 0)])
+            .collect()];
+        for step in 1..candidates.len() {
+            let previous_paths = &paths[step - 1];
+            let reached = candidates[step]
+                .iter()
+                .map(|(voices, cost)| {
+                    let mut reaching: Vec<_> = candidates[step - 1]
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(previous, (previous_voices, _))| {
+                            let transition = self.transition_cost(previous_voices, voices) + cost;
+                            previous_paths[previous].iter().enumerate().map(
+                                move |(rank, (path_cost, _, _))| {
+                                    (path_cost + transition, previous, rank)
+                                },
+                            )
+                        })
+                        .collect();
+                    reaching.sort_unstable();
+                    reaching.truncate(count);
+                    reaching
+                })
+                .collect();
+            paths.push(reached);
+        }
+
+        let last = candidates.len() - 1;
+        let mut ends: Vec<_> = paths[last]
+            .iter()
+            .enumerate()
+            .flat_map(|(candidate, reaching)| {
+                reaching
+                    .iter()
+                    .enumerate()
+                    .map(move |(rank, (cost, _, _))| (*cost, candidate, rank))
+            })
+            .collect();
+        ends.sort_unstable();
+        ends.truncate(count);
+
+        ends.into_iter()
+            .map(|(cost, mut candidate, mut rank)| {
+                let mut voices = Vec::with_capacity(candidates.len());
+                for step in (0..candidates.len()).rev() {
+                    voices.push(candidates[step][candidate].0);
+                    let (_, previous, previous_rank) = paths[step][candidate][rank];
+                    (candidate, rank) = (previous, previous_rank);
+                }
+                voices.reverse();
+                Voicing { voices, cost }
+            })
+            .collect()
+    }
+
+    /// Every voicing of `chord` within the ranges with its own cost, leaving out at most its
+    /// fifth and any tones beyond the fourth.
+    fn candidates(&self, chord: &harmony::RootedChordClass) -> Vec<([pitch::NotePitch; 4], u32)> {
+        let root = chord.root();
+        let classes = chord.chord_class().note_pitch_classes();
+        let has_third = classes
+            .iter()
+            .any(|class| matches!(above(root, *class), 3 | 4));
+        let leading_tone =
+            pitch::NotePitchClass::from_semitones(self.key.tonic().note_pitch_class() as i32 - 1);
+        let pitches_in = |voice: Voice| -> Vec<pitch::NotePitch> {
+            let (low, high) = self.range(voice);
+            (low.semitones()..=high.semitones())
+                .map(note_pitch)
+                .filter(|note_pitch| classes.contains(&note_pitch.class()))
+                .collect()
+        };
+        let [bass, tenor, alto, soprano] = Voice::ALL.map(pitches_in);
+
+        let mut candidates = Vec::new();
+        for b in &bass {
+            for t in tenor.iter().filter(|t| *t > b) {
+                for a in alto
+                    .iter()
+                    .filter(|a| *a > t && a.semitones() - t.semitones() <= 12)
+                {
+                    for s in soprano
+                        .iter()
+                        .filter(|s| *s > a && s.semitones() - a.semitones() <= 12)
+                    {
+                        let voices = [*b, *t, *a, *s];
+                        let cost = chord_cost(&voices, root, classes, has_third, leading_tone);
+                        if let Some(cost) = cost {
+                            candidates.push((voices, cost));
+                        }
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    fn transition_cost(&self, from: &[pitch::NotePitch; 4], to: &[pitch::NotePitch; 4]) -> u32 {
+        let motions = from
+            .iter()
+            .zip(to)
+            .map(|(from, to)| VoiceMotion::new(*from, *to))
+            .collect();
+        let voice_leading = VoiceLeading::new(motions, Some(&self.key));
+        let faults: u32 = voice_leading
+            .faults()
+            .iter()
+            .map(|fault| match fault.kind() {
+                FaultKind::ParallelFifths
+                | FaultKind::ParallelOctaves
+                | FaultKind::VoiceCrossing => 100,
+                FaultKind::UnresolvedLeadingTone | FaultKind::UnresolvedSeventh => 40,
+                FaultKind::VoiceOverlap => 30,
+                FaultKind::HiddenFifths | FaultKind::HiddenOctaves | FaultKind::Spacing => 20,
+            })
+            .sum();
+        faults + voice_leading.displacement()
+    }
+}
+
+/// The semitones from `root` up to `class` within an octave.
+fn above(root: pitch::NotePitchClass, class: pitch::NotePitchClass) -> Semitones {
+    (class as i32 - root as i32).rem_euclid(12)
+}
+
+fn note_pitch(semitones: Semitones) -> pitch::NotePitch {
+    pitch::NotePitch::new(
+        pitch::NotePitchClass::from_semitones(semitones),
+        semitones.div_euclid(12),
+    )
+}
+
+/// The cost of a single voicing, or `None` if it leaves out the root or more than its fifth
+/// and the tones beyond the fourth.
+fn chord_cost(
+    voices: &[pitch::NotePitch; 4],
+    root: pitch::NotePitchClass,
+    classes: &HashSet<pitch::NotePitchClass>,
+    has_third: bool,
+    leading_tone: pitch::NotePitchClass,
+) -> Option<u32> {
+    let used: HashSet<_> = voices.iter().map(pitch::NotePitch::class).collect();
+    let missing: Vec<_> = classes
+        .iter()
+        .filter(|class| !used.contains(class))
+        .collect();
+    let fifth_missing = missing.iter().any(|class| above(root, **class) == 7);
+    let required_missing = missing.len() - usize::from(fifth_missing);
+    if !used.contains(&root) || required_missing > classes.len().saturating_sub(4) {
+        return None;
+    }
+
+    let mut cost = if fifth_missing { 2 } else { 0 };
+    cost += match above(root, voices[0].class()) {
+        0 => 0,
+        3 | 4 if has_third => 4,
+        _ => 8,
+    };
+    for class in &used {
+        let doubled = voices
+            .iter()
+            .filter(|voice| voice.class() == *class)
+            .count();
+        if doubled < 2 {
+            continue;
+        }
+        cost += if *class == leading_tone {
+            30
+        } else {
+            match above(root, *class) {
+                0 => 0,
+                7 => 2,
+                10 | 11 => 30,
+                _ => 4,
+            }
+        };
+    }
+    Some(cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::pitch::SpelledNotePitchClass;
+
+    use super::*;
+
+    fn c_major() -> key::Key {
+        key::Key::major(SpelledNotePitchClass::from_str("C").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn cadence_follows_the_rules() {
+        let key = c_major();
+        let triads = key.diatonic_triads();
+        let sevenths = key.diatonic_seventh_chords();
+        let progression = [
+            triads[0].clone(),
+            triads[3].clone(),
+            sevenths[4].clone(),
+            triads[0].clone(),
+        ];
+        let voicer = FourPartVoicer::new(key);
+        let voicings = voicer.voice(&progression, 5);
+        assert_eq!(voicings.len(), 5);
+        assert!(voicings
+            .windows(2)
+            .all(|pair| pair[0].cost() <= pair[1].cost()));
+
+        let best = &voicings[0];
+        for (chord, voices) in progression.iter().zip(best.voices()) {
+            assert_eq!(voices[0].class(), chord.root());
+            for (voice, note_pitch) in Voice::ALL.iter().zip(voices) {
+                let (low, high) = voicer.range(*voice);
+                assert!(low <= *note_pitch && *note_pitch <= high);
+            }
+        }
+        let chords = best.chords();
+        for voice_leading in VoiceLeading::progression(&chords, Some(&key)) {
+            assert!(voice_leading.faults().is_empty(), "{best:?}");
+        }
+    }
+
+    #[test]
+    fn narrow_ranges() {
+        use pitch::NotePitchClass::{C, D};
+        let key = c_major();
+        let tonic = key.diatonic_triads()[0].clone();
+        let voicer = FourPartVoicer::new(key).with_range(
+            Voice::Soprano,
+            pitch::NotePitch::new(C, 5),
+            pitch::NotePitch::new(D, 5),
+        );
+        let voicings = voicer.voice(&[tonic], 100);
+        assert!(!voicings.is_empty());
+        assert!(voicings
+            .iter()
+            .all(|voicing| voicing.voices()[0][3] == pitch::NotePitch::new(C, 5)));
+        assert!(voicer.voice(&[], 1).is_empty());
+    }
+}